@@ -1,10 +1,11 @@
 use bevy::prelude::*;
 
-use crate::components::utils::Cleanable;
+use crate::components::{utils::Cleanable, weapons::Weapon};
 
 use super::stats::{Health, MoveSpeedMultiplier, MoveSpeedStat};
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 #[require(Transform, MoveSpeedStat, MoveSpeedMultiplier, Health, AITarget, Cleanable)]
 pub struct AI {
 	pub enabled: bool,
@@ -34,12 +35,16 @@ impl AITarget {
 	}
 }
 
-#[derive(Component, Reflect)]
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
 #[require(AI, ChargeInfo, MoveSpeedMultiplier)]
 pub struct ChargeAI {
 	pub distance: f32,
 	pub speed_multi: f32,
 	pub hit_damage: f32,
+	//Goes back to chasing after a charge instead of spending itself on it
+	#[reflect(default)]
+	pub repeat: bool,
 }
 
 #[derive(Component, Reflect)]
@@ -70,10 +75,12 @@ pub enum ChargeState {
 }
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 #[require(AI)]
 pub struct ChaseAI;
 
-#[derive(Component, Reflect)]
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
 #[require(AI)]
 pub struct HoverAI {
 	pub hover_distance: f32,
@@ -102,3 +109,22 @@ impl HoverAI {
 		return self.min_distance_squared() <= dist_squared && self.max_distance_squared() >= dist_squared;
 	}
 }
+
+//Fires its weapon at the player while they are in range and roughly in front of it
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[require(AI, Weapon)]
+pub struct ShooterAI {
+	pub range: f32,
+	//Degrees either side of facing the player
+	pub aim_tolerance: f32,
+}
+
+impl ShooterAI {
+	pub fn can_hit(&self, facing: Vec2, to_target: Vec2) -> bool {
+		if to_target.length_squared() > self.range * self.range {
+			return false;
+		}
+		return facing.angle_to(to_target).abs().to_degrees() <= self.aim_tolerance;
+	}
+}
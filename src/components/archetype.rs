@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
+pub struct EnemyArchetype(pub String);
+
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
+pub struct EnemyBody {
+	pub shape: BodyShape,
+	pub color: LinearRgba,
+	pub collider: f32,
+}
+
+#[derive(Reflect, Clone, Copy)]
+pub enum BodyShape {
+	Circle { radius: f32 },
+	Capsule { radius: f32, length: f32 },
+	Polygon { radius: f32, sides: u32 },
+}
+
+impl BodyShape {
+	pub fn mesh(&self) -> Mesh {
+		match *self {
+			BodyShape::Circle { radius } => Circle::new(radius).into(),
+			BodyShape::Capsule { radius, length } => Capsule2d::new(radius, length).into(),
+			BodyShape::Polygon { radius, sides } => RegularPolygon::new(radius, sides).into(),
+		}
+	}
+}
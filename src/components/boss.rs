@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+
+use crate::components::{
+	ai::{ChargeAI, HoverAI},
+	death::DeathScatter,
+};
+
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
+#[require(BossState)]
+pub struct Boss {
+	pub name: String,
+	//How it moves whenever the current phase isn't charging
+	pub hover: HoverAI,
+	//Ordered from full health down
+	pub phases: Vec<BossPhase>,
+}
+
+impl Boss {
+	//The last phase whose threshold the health fraction has dropped to
+	pub fn phase_at(&self, fraction: f32) -> usize {
+		return self
+			.phases
+			.iter()
+			.rposition(|phase| fraction <= phase.threshold)
+			.unwrap_or(0);
+	}
+}
+
+#[derive(Reflect, Clone)]
+pub struct BossPhase {
+	//Fraction of max health the phase starts at
+	pub threshold: f32,
+	pub attacks: Vec<BossAttack>,
+}
+
+#[derive(Reflect, Clone)]
+pub enum BossAttack {
+	//Sets off the scatter from wherever the boss is every interval
+	Barrage {
+		interval: f32,
+		scatter: DeathScatter,
+	},
+	Charge(ChargeAI),
+	Summon {
+		interval: f32,
+		archetype: String,
+		count: u32,
+	},
+}
+
+impl BossAttack {
+	pub fn timer(&self) -> Timer {
+		return match self {
+			BossAttack::Barrage { interval, .. } | BossAttack::Summon { interval, .. } => {
+				Timer::from_seconds(*interval, TimerMode::Repeating)
+			}
+			BossAttack::Charge(_) => Timer::default(),
+		};
+	}
+}
+
+#[derive(Component, Reflect, Default)]
+pub struct BossState {
+	pub phase: Option<usize>,
+	//One per attack of the current phase
+	pub timers: Vec<Timer>,
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-use crate::components::stats::Life;
+use crate::components::{stats::Life, status::StatusApplication, tags::Owner};
 
 #[derive(Component, Reflect)]
 pub struct DeathExplosion {
@@ -9,20 +10,108 @@ pub struct DeathExplosion {
 	pub damage: f32,
 }
 
-#[derive(Component, Reflect, Default, Clone, Copy)]
+//Stages set off once the entity dies, it sticks around until the last one is done
+#[derive(Component, Reflect, Default, Clone)]
+#[reflect(Component)]
 #[require(Life, Transform)]
 pub struct DeathScatter {
+	pub stages: Vec<ScatterStage>,
+}
+
+impl From<ScatterStage> for DeathScatter {
+	fn from(stage: ScatterStage) -> Self {
+		return DeathScatter { stages: vec![stage] };
+	}
+}
+
+#[derive(Reflect, Deserialize, Default, Clone, Copy)]
+pub struct ScatterStage {
 	pub count: u32,
 	pub pattern: ScatterPattern,
 	pub damage: f32,
+	//Applied by everything the scatter spawns
+	#[reflect(default)]
+	#[serde(default)]
+	pub status: Option<StatusApplication>,
+	//Push given by everything the scatter spawns
+	#[reflect(default)]
+	#[serde(default)]
+	pub knockback: f32,
+	//Seconds after death before the stage first goes off
+	#[reflect(default)]
+	#[serde(default)]
+	pub delay: f32,
+	//Extra times the stage goes off after the first
+	#[reflect(default)]
+	#[serde(default)]
+	pub repeat: u32,
+	//Seconds between repeats
+	#[reflect(default)]
+	#[serde(default)]
+	pub interval: f32,
+}
+
+//Side a scatter that isn't carried by a projectile is on, enemy deaths stay on the enemy side without it
+#[derive(Component, Reflect, Clone, Copy)]
+pub struct ScatterOwner(pub Owner);
+
+//Where each stage of a triggered DeathScatter is up to
+#[derive(Component, Reflect)]
+pub struct ScatterProgress {
+	pub chain: ChainLink,
+	pub stages: Vec<StageProgress>,
+}
+
+#[derive(Reflect)]
+pub struct StageProgress {
+	pub timer: Timer,
+	pub remaining: u32,
+}
+
+impl StageProgress {
+	pub fn new(stage: &ScatterStage) -> Self {
+		return Self {
+			timer: Timer::from_seconds(stage.delay, TimerMode::Once),
+			remaining: stage.repeat + 1,
+		};
+	}
+}
+
+impl ScatterProgress {
+	pub fn is_finished(&self) -> bool {
+		return self.stages.iter().all(|stage| stage.remaining == 0);
+	}
 }
 
-#[derive(Reflect, Clone, Copy)]
+#[derive(Reflect, Deserialize, Clone, Copy)]
 pub enum ScatterPattern {
 	Explosion { range: f32, speed: f32 },
 	Spread { arc: f32, targeting: Targeting },
 	Spiral { angle: f32, rate: f32 },
+	//Evenly around the full circle, turned by offset degrees
+	Ring { offset: f32 },
+	//Slower projectiles that steer toward the nearest thing they can hit
+	Homing { turn_rate: f32, range: f32 },
+	//Left lying around the death, each going off as an explosion after the fuse
+	Mines { radius: f32, fuse: f32, range: f32 },
+	//Arcs straight to the nearest targets in turn, losing falloff of its damage on each jump
+	Chain { range: f32, falloff: f32 },
 }
+
+impl ScatterPattern {
+	pub fn label(&self) -> &'static str {
+		match self {
+			ScatterPattern::Explosion { .. } => "Explosion",
+			ScatterPattern::Spread { .. } => "Spread",
+			ScatterPattern::Spiral { .. } => "Spiral",
+			ScatterPattern::Ring { .. } => "Ring",
+			ScatterPattern::Homing { .. } => "Homing Swarm",
+			ScatterPattern::Mines { .. } => "Mine Field",
+			ScatterPattern::Chain { .. } => "Lightning Chain",
+		}
+	}
+}
+
 impl Default for ScatterPattern {
 	fn default() -> Self {
 		Self::Explosion {
@@ -32,13 +121,21 @@ impl Default for ScatterPattern {
 	}
 }
 
-#[derive(Reflect, Clone, Copy)]
+#[derive(Reflect, Deserialize, Clone, Copy)]
 pub enum Targeting {
 	Forward,
 	Random,
 	Player,
 }
 
+//Sits still until its Lifetime runs out and the DeathScatter it carries goes off
+#[derive(Component, Reflect)]
+pub struct Mine;
+
+//Drawn between each target a lightning chain jumps to
+#[derive(Component, Reflect)]
+pub struct ChainArc;
+
 #[derive(Component, Reflect, Default)]
 pub struct SpiralSpawner {
 	pub timer: Timer,
@@ -46,6 +143,36 @@ pub struct SpiralSpawner {
 	pub spawn_count: u32,
 	pub angle: f32,
 	pub damage: f32,
+	pub status: Option<StatusApplication>,
+	pub knockback: f32,
+	pub chain: ChainLink,
+	pub owner: Owner,
 	pub mesh: Handle<Mesh>,
 	pub material: Handle<ColorMaterial>,
 }
+
+//Chain reaction an entity belongs to, generation 0 being the death that started it
+#[derive(Component, Reflect, Default, Clone, Copy)]
+pub struct ChainLink {
+	pub chain: u32,
+	pub generation: u32,
+}
+
+impl ChainLink {
+	//Carried by what enemies set off themselves, kills through it don't count towards any chain
+	pub const ENEMY: ChainLink = ChainLink {
+		chain: u32::MAX,
+		generation: 0,
+	};
+
+	pub fn is_enemy(&self) -> bool {
+		return self.chain == Self::ENEMY.chain;
+	}
+
+	pub fn next(&self) -> Self {
+		ChainLink {
+			chain: self.chain,
+			generation: self.generation + 1,
+		}
+	}
+}
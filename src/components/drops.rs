@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+
+use crate::{
+	components::{tags::Pickup, weapons::Weapon},
+	resources::upgrades::Rarity,
+};
+
+//What an enemy archetype can leave behind when it dies, anything without one uses the default table
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component)]
+pub struct DropTable {
+	//Odds of a drop on each death, before pity
+	pub chance: f32,
+	//Drops given on every death regardless of the roll
+	#[reflect(default)]
+	pub guaranteed: u32,
+	pub entries: Vec<DropEntry>,
+}
+
+#[derive(Reflect, Clone, Copy)]
+pub struct DropEntry {
+	pub pickup: Pickup,
+	pub rarity: Rarity,
+}
+
+impl DropEntry {
+	pub fn new(pickup: Pickup, rarity: Rarity) -> Self {
+		return Self { pickup, rarity };
+	}
+}
+
+impl Default for DropTable {
+	fn default() -> Self {
+		Self {
+			chance: 0.05,
+			guaranteed: 0,
+			entries: vec![
+				DropEntry::new(Pickup::Health, Rarity::Common),
+				DropEntry::new(Pickup::Stats, Rarity::Common),
+				DropEntry::new(Pickup::Weapon(Weapon::Auto), Rarity::Common),
+				DropEntry::new(Pickup::Weapon(Weapon::Burst), Rarity::Common),
+				DropEntry::new(Pickup::Weapon(Weapon::Spread), Rarity::Common),
+				DropEntry::new(Pickup::Weapon(Weapon::Beam), Rarity::Common),
+			],
+		}
+	}
+}
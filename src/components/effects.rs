@@ -1,11 +1,28 @@
 use bevy::prelude::*;
 
+use crate::components::tags::Owner;
+
 #[derive(Component, Reflect)]
 #[require(ExplosionProgress, Transform)]
 pub struct Explosion {
 	pub range: f32,
 	pub epansion_rate: f32,
+	pub owner: Owner,
 }
 
 #[derive(Component, Reflect, Default)]
 pub struct ExplosionProgress(pub f32);
+
+//Original color of an entity that was given its own material to tint or flash
+#[derive(Component, Reflect)]
+pub struct BaseColor(pub Color);
+
+//Briefly flashes the entity white after a hit
+#[derive(Component, Reflect)]
+pub struct HitFlash(pub Timer);
+
+impl Default for HitFlash {
+	fn default() -> Self {
+		HitFlash(Timer::from_seconds(0.1, TimerMode::Once))
+	}
+}
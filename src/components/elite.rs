@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+//Promoted spawn, each affix changes how it fights or dies
+#[derive(Component, Reflect, Clone, Default)]
+#[reflect(Component)]
+pub struct Elite(pub Vec<Affix>);
+
+impl Elite {
+	pub fn has(&self, affix: Affix) -> bool {
+		return self.0.contains(&affix);
+	}
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Affix {
+	//Soaks up damage before its health
+	Shielded,
+	Hasted,
+	//Breaks into smaller copies of itself on death
+	Splitting,
+	//Heals off enemies dying around it
+	Vampiric,
+	//Scatters twice as much on death
+	Volatile,
+	//Blinks next to the player every so often
+	Teleporting,
+}
+
+impl Affix {
+	pub const ALL: [Affix; 6] = [
+		Affix::Shielded,
+		Affix::Hasted,
+		Affix::Splitting,
+		Affix::Vampiric,
+		Affix::Volatile,
+		Affix::Teleporting,
+	];
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			Affix::Shielded => "Shielded",
+			Affix::Hasted => "Hasted",
+			Affix::Splitting => "Splitting",
+			Affix::Vampiric => "Vampiric",
+			Affix::Volatile => "Volatile",
+			Affix::Teleporting => "Teleporting",
+		}
+	}
+
+	pub fn color(&self) -> LinearRgba {
+		match self {
+			Affix::Shielded => LinearRgba::rgb(0.4, 0.8, 3.0),
+			Affix::Hasted => LinearRgba::rgb(3.0, 3.0, 0.4),
+			Affix::Splitting => LinearRgba::rgb(0.4, 3.0, 0.6),
+			Affix::Vampiric => LinearRgba::rgb(3.0, 0.1, 0.2),
+			Affix::Volatile => LinearRgba::rgb(3.0, 1.0, 0.1),
+			Affix::Teleporting => LinearRgba::rgb(2.0, 0.3, 3.0),
+		}
+	}
+}
+
+#[derive(Component, Reflect)]
+pub struct Shield(pub f32);
+
+impl Shield {
+	//Returns what gets through
+	pub fn absorb(&mut self, amount: f32) -> f32 {
+		let absorbed = amount.min(self.0);
+		self.0 -= absorbed;
+		return amount - absorbed;
+	}
+}
+
+#[derive(Component, Reflect)]
+pub struct Teleporter(pub Timer);
+
+//Ring drawn under an elite in the color of one of its affixes, pulsing around its base scale
+#[derive(Component, Reflect)]
+pub struct EliteAura(pub f32);
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::resources::replay::InputFrame;
+
+//What the player wants to do this tick, the only input gameplay systems read
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Debug)]
+#[reflect(Component)]
+pub struct PlayerIntent {
+	pub move_dir: Vec2,
+	pub aim_dir: Vec2,
+	pub fire: bool,
+	//Slot 1-5 of the default projectile types to switch to
+	pub select: Option<u8>,
+	pub dash: bool,
+	//Choice 1-3 of an open level-up draft
+	pub pick: Option<u8>,
+	//Slot of the weapon inventory to switch to
+	pub weapon: Option<u8>,
+	pub next_weapon: bool,
+}
+
+impl Default for PlayerIntent {
+	fn default() -> Self {
+		Self {
+			move_dir: Vec2::ZERO,
+			aim_dir: Vec2::Y,
+			fire: false,
+			select: None,
+			dash: false,
+			pick: None,
+			weapon: None,
+			next_weapon: false,
+		}
+	}
+}
+
+impl From<InputFrame> for PlayerIntent {
+	fn from(frame: InputFrame) -> Self {
+		Self {
+			move_dir: frame.move_dir(),
+			aim_dir: frame.aim_dir(),
+			fire: frame.fire,
+			select: (frame.select != 0).then_some(frame.select),
+			dash: frame.dash,
+			pick: (frame.pick != 0).then_some(frame.pick),
+			weapon: (frame.weapon != 0).then_some(frame.weapon),
+			next_weapon: frame.next_weapon,
+		}
+	}
+}
@@ -1,8 +1,14 @@
 pub mod ai;
+pub mod archetype;
+pub mod boss;
 pub mod death;
+pub mod drops;
 pub mod effects;
+pub mod elite;
+pub mod input;
 pub mod spawner;
 pub mod stats;
+pub mod status;
 pub mod tags;
 pub mod ui;
 pub mod utils;
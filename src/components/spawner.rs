@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 #[derive(Component, Reflect)]
 #[require(Transform, SpawnBatch)]
@@ -10,9 +11,15 @@ pub struct Spawner {
 	pub spawn_speed: Timer,
 	pub min_batch_size: u32,
 	pub max_batch_size: u32,
-	pub prefabs: Vec<Entity>,
+	pub enemies: Vec<SpawnEntry>,
 	pub spawn_effect: Entity,
 }
 
 #[derive(Component, Default, Reflect)]
 pub struct SpawnBatch(pub u32);
+
+#[derive(Reflect, Deserialize, Clone)]
+pub struct SpawnEntry {
+	pub archetype: String,
+	pub weight: f32,
+}
@@ -1,7 +1,13 @@
+use std::borrow::Cow;
+
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::resources::utils::DamageKind;
 
 #[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct Damage(pub f32);
 
 impl From<f32> for Damage {
@@ -10,11 +16,139 @@ impl From<f32> for Damage {
 	}
 }
 
+//Fraction of each damage type that is ignored, negative for weaknesses
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct Resistances {
+	pub kinetic: f32,
+	pub explosive: f32,
+	pub energy: f32,
+	pub contact: f32,
+	pub toxic: f32,
+}
+
+impl Resistances {
+	pub fn get(&self, kind: DamageKind) -> f32 {
+		match kind {
+			DamageKind::Kinetic => self.kinetic,
+			DamageKind::Explosive => self.explosive,
+			DamageKind::Energy => self.energy,
+			DamageKind::Contact => self.contact,
+			DamageKind::Toxic => self.toxic,
+		}
+	}
+}
+
+//Flat reduction taken off every hit after resistances
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct Armor(pub f32);
+
+//Levels come from experience dropped by enemies, each one opens an upgrade draft
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Experience {
+	pub level: u32,
+	pub xp: u32,
+}
+
+impl Default for Experience {
+	fn default() -> Self {
+		Self { level: 1, xp: 0 }
+	}
+}
+
+impl Experience {
+	pub const BASE: u32 = 5;
+	pub const GROWTH: u32 = 4;
+
+	//Xp needed to go from the current level to the next
+	pub fn required(&self) -> u32 {
+		Self::BASE + Self::GROWTH * (self.level - 1)
+	}
+
+	//Returns how many levels were gained
+	pub fn add(&mut self, xp: u32) -> u32 {
+		self.xp += xp;
+		let mut levels = 0;
+		while self.xp >= self.required() {
+			self.xp -= self.required();
+			self.level += 1;
+			levels += 1;
+		}
+		return levels;
+	}
+}
+
+//Ignores damage for a while after being hit
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct IFrames {
+	//How long a hit protects for
+	pub duration: f32,
+	pub timer: Timer,
+}
+
+impl IFrames {
+	//Starts out vulnerable
+	pub fn new(secs: f32) -> Self {
+		let mut timer = Timer::from_seconds(secs, TimerMode::Once);
+		timer.tick(timer.duration());
+		return IFrames { duration: secs, timer };
+	}
+
+	pub fn is_active(&self) -> bool {
+		!self.timer.finished()
+	}
+
+	pub fn hit(&mut self) {
+		self.grant(self.duration);
+	}
+
+	//Never cuts short protection that would last longer
+	pub fn grant(&mut self, secs: f32) {
+		if self.timer.remaining_secs() < secs {
+			self.timer = Timer::from_seconds(secs, TimerMode::Once);
+		}
+	}
+}
+
+//Strength of the push given to whatever this hits, away from where it hit from
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct Knockback(pub f32);
+
+//Carried by whatever deals the damage, projectiles take it from the shooter's stats
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+pub struct Critical {
+	pub chance: f32,
+	pub multiplier: f32,
+}
+
+impl Default for Critical {
+	fn default() -> Self {
+		Self {
+			chance: 0.,
+			multiplier: 1.,
+		}
+	}
+}
+
+impl From<&StatBlock> for Critical {
+	fn from(stats: &StatBlock) -> Self {
+		Self {
+			chance: stats.crit_chance,
+			multiplier: stats.crit_multiplier,
+		}
+	}
+}
+
 #[derive(Component, Default, Reflect)]
 #[require(RigidBody, Velocity, ExternalForce, Damping)]
 pub struct MoveSpeed(pub f32);
 
 #[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 #[require(MoveSpeed)]
 pub struct MoveSpeedStat(pub f32);
 
@@ -57,10 +191,12 @@ impl Life {
 }
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 #[require(Health)]
 pub struct MaxHealth(pub f32);
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 #[require(Health, MaxHealth)]
 pub struct HealthRegen(pub f32);
 
@@ -91,3 +227,283 @@ impl FireRate {
 		return FireRate(Timer::from_seconds(1.0 / rate, TimerMode::Repeating));
 	}
 }
+
+#[derive(Reflect, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatKind {
+	MaxHealth,
+	Regen,
+	MoveSpeed,
+	Damage,
+	FireRate,
+	ProjectileSpeed,
+	PickupRange,
+	CritChance,
+	CritMultiplier,
+	DashDistance,
+	DashCooldown,
+}
+
+impl StatKind {
+	pub fn label(&self) -> &'static str {
+		match self {
+			StatKind::MaxHealth => "Max Health",
+			StatKind::Regen => "Regen",
+			StatKind::MoveSpeed => "Move Speed",
+			StatKind::Damage => "Damage",
+			StatKind::FireRate => "Fire Rate",
+			StatKind::ProjectileSpeed => "Projectile Speed",
+			StatKind::PickupRange => "Pickup Range",
+			StatKind::CritChance => "Crit Chance",
+			StatKind::CritMultiplier => "Crit Damage",
+			StatKind::DashDistance => "Dash Distance",
+			StatKind::DashCooldown => "Dash Cooldown",
+		}
+	}
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Debug)]
+pub struct StatBlock {
+	pub max_health: f32,
+	//Health per second
+	pub regen: f32,
+	pub move_speed: f32,
+	//Damage, fire rate and projectile speed scale the weapon's own values
+	pub damage: f32,
+	pub fire_rate: f32,
+	pub projectile_speed: f32,
+	pub pickup_range: f32,
+	pub crit_chance: f32,
+	pub crit_multiplier: f32,
+	pub dash_distance: f32,
+	//Seconds between dashes
+	pub dash_cooldown: f32,
+}
+
+impl Default for StatBlock {
+	fn default() -> Self {
+		Self {
+			max_health: 100.,
+			regen: 0.,
+			move_speed: 100.,
+			damage: 1.,
+			fire_rate: 1.,
+			projectile_speed: 1.,
+			pickup_range: 200.,
+			crit_chance: 0.,
+			crit_multiplier: 2.,
+			dash_distance: 150.,
+			dash_cooldown: 1.5,
+		}
+	}
+}
+
+impl StatBlock {
+	pub fn get(&self, stat: StatKind) -> f32 {
+		match stat {
+			StatKind::MaxHealth => self.max_health,
+			StatKind::Regen => self.regen,
+			StatKind::MoveSpeed => self.move_speed,
+			StatKind::Damage => self.damage,
+			StatKind::FireRate => self.fire_rate,
+			StatKind::ProjectileSpeed => self.projectile_speed,
+			StatKind::PickupRange => self.pickup_range,
+			StatKind::CritChance => self.crit_chance,
+			StatKind::CritMultiplier => self.crit_multiplier,
+			StatKind::DashDistance => self.dash_distance,
+			StatKind::DashCooldown => self.dash_cooldown,
+		}
+	}
+
+	pub fn get_mut(&mut self, stat: StatKind) -> &mut f32 {
+		match stat {
+			StatKind::MaxHealth => &mut self.max_health,
+			StatKind::Regen => &mut self.regen,
+			StatKind::MoveSpeed => &mut self.move_speed,
+			StatKind::Damage => &mut self.damage,
+			StatKind::FireRate => &mut self.fire_rate,
+			StatKind::ProjectileSpeed => &mut self.projectile_speed,
+			StatKind::PickupRange => &mut self.pickup_range,
+			StatKind::CritChance => &mut self.crit_chance,
+			StatKind::CritMultiplier => &mut self.crit_multiplier,
+			StatKind::DashDistance => &mut self.dash_distance,
+			StatKind::DashCooldown => &mut self.dash_cooldown,
+		}
+	}
+}
+
+//Stats before any modifiers
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+#[require(Stats, StatModifiers)]
+pub struct BaseStats(pub StatBlock);
+
+//Stats after modifiers, recomputed whenever the stack or the base changes
+#[derive(Component, Reflect, Default)]
+pub struct Stats(pub StatBlock);
+
+//Applied in order: (base + flat) * (1 + sum of additive percents) * every multiplier
+#[derive(Reflect, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ModifierOp {
+	Flat(f32),
+	AddPercent(f32),
+	Multiply(f32),
+}
+
+impl ModifierOp {
+	//e.g. "+10% Fire Rate", crit chance is a fraction so flat amounts show as percents too
+	pub fn describe(&self, stat: StatKind) -> String {
+		let label = stat.label();
+		match self {
+			ModifierOp::Flat(value) if stat == StatKind::CritChance => format!("{:+.0}% {label}", value * 100.),
+			ModifierOp::Flat(value) => format!("{value:+} {label}"),
+			ModifierOp::AddPercent(value) => format!("{:+.0}% {label}", value * 100.),
+			ModifierOp::Multiply(value) => format!("x{value} {label}"),
+		}
+	}
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct StatModifier {
+	pub stat: StatKind,
+	pub op: ModifierOp,
+	//What granted it, so it can be refreshed or removed as a group
+	pub source: Cow<'static, str>,
+	//None for permanent modifiers
+	pub duration: Option<Timer>,
+}
+
+impl StatModifier {
+	pub fn permanent(stat: StatKind, op: ModifierOp, source: impl Into<Cow<'static, str>>) -> Self {
+		Self {
+			stat,
+			op,
+			source: source.into(),
+			duration: None,
+		}
+	}
+
+	pub fn timed(stat: StatKind, op: ModifierOp, source: impl Into<Cow<'static, str>>, secs: f32) -> Self {
+		Self {
+			duration: Some(Timer::from_seconds(secs, TimerMode::Once)),
+			..Self::permanent(stat, op, source)
+		}
+	}
+
+	pub fn is_expired(&self) -> bool {
+		self.duration.as_ref().is_some_and(Timer::finished)
+	}
+}
+
+#[derive(Component, Reflect, Default)]
+pub struct StatModifiers(pub Vec<StatModifier>);
+
+impl StatModifiers {
+	pub fn add(&mut self, modifier: StatModifier) {
+		self.0.push(modifier);
+	}
+
+	//Replaces the modifier from the same source on the same stat, so reapplying a buff restarts it instead of stacking
+	pub fn refresh(&mut self, modifier: StatModifier) {
+		self.0
+			.retain(|existing| existing.stat != modifier.stat || existing.source != modifier.source);
+		self.0.push(modifier);
+	}
+
+	pub fn remove_source(&mut self, source: &str) {
+		self.0.retain(|modifier| modifier.source != source);
+	}
+
+	pub fn resolve(&self, stat: StatKind, base: f32) -> f32 {
+		let mut flat = 0.;
+		let mut percent = 0.;
+		let mut multiplier = 1.;
+		for modifier in self.0.iter().filter(|modifier| modifier.stat == stat) {
+			match modifier.op {
+				ModifierOp::Flat(value) => flat += value,
+				ModifierOp::AddPercent(value) => percent += value,
+				ModifierOp::Multiply(value) => multiplier *= value,
+			}
+		}
+		return (base + flat) * (1. + percent) * multiplier;
+	}
+
+	pub fn resolve_all(&self, base: &StatBlock) -> StatBlock {
+		let mut stats = *base;
+		for stat in [
+			StatKind::MaxHealth,
+			StatKind::Regen,
+			StatKind::MoveSpeed,
+			StatKind::Damage,
+			StatKind::FireRate,
+			StatKind::ProjectileSpeed,
+			StatKind::PickupRange,
+			StatKind::CritChance,
+			StatKind::CritMultiplier,
+			StatKind::DashDistance,
+			StatKind::DashCooldown,
+		] {
+			*stats.get_mut(stat) = self.resolve(stat, base.get(stat));
+		}
+		return stats;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resolve_adds_flat_then_sums_percents_then_multiplies() {
+		let mut modifiers = StatModifiers::default();
+		modifiers.add(StatModifier::permanent(StatKind::Damage, ModifierOp::Multiply(2.), "a"));
+		modifiers.add(StatModifier::permanent(
+			StatKind::Damage,
+			ModifierOp::AddPercent(0.25),
+			"b",
+		));
+		modifiers.add(StatModifier::permanent(StatKind::Damage, ModifierOp::Flat(2.), "c"));
+		modifiers.add(StatModifier::permanent(
+			StatKind::Damage,
+			ModifierOp::AddPercent(0.25),
+			"d",
+		));
+		modifiers.add(StatModifier::permanent(
+			StatKind::Damage,
+			ModifierOp::Multiply(1.5),
+			"e",
+		));
+		assert_eq!(modifiers.resolve(StatKind::Damage, 8.), (8. + 2.) * 1.5 * 2. * 1.5);
+	}
+
+	#[test]
+	fn resolve_ignores_other_stats() {
+		let mut modifiers = StatModifiers::default();
+		modifiers.add(StatModifier::permanent(
+			StatKind::FireRate,
+			ModifierOp::Multiply(3.),
+			"a",
+		));
+		assert_eq!(modifiers.resolve(StatKind::MoveSpeed, 5.), 5.);
+		assert_eq!(modifiers.resolve(StatKind::FireRate, 5.), 15.);
+	}
+
+	#[test]
+	fn refreshing_replaces_instead_of_stacking() {
+		let mut modifiers = StatModifiers::default();
+		modifiers.add(StatModifier::timed(
+			StatKind::MoveSpeed,
+			ModifierOp::AddPercent(0.5),
+			"haste",
+			1.,
+		));
+		modifiers.refresh(StatModifier::timed(
+			StatKind::MoveSpeed,
+			ModifierOp::AddPercent(0.5),
+			"haste",
+			1.,
+		));
+		assert_eq!(modifiers.resolve(StatKind::MoveSpeed, 2.), 3.);
+		modifiers.remove_source("haste");
+		assert_eq!(modifiers.resolve(StatKind::MoveSpeed, 2.), 2.);
+	}
+}
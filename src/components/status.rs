@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{components::tags::Owner, resources::utils::DamageKind};
+
+//Damage over time ticks in pulses rather than every frame
+pub const STATUS_PULSE: f32 = 0.5;
+//How long each shock pulse stuns for
+const SHOCK_STUN: f32 = 0.15;
+
+#[derive(Reflect, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatusKind {
+	Burn,
+	Poison,
+	Slow,
+	Freeze,
+	Shock,
+}
+
+impl StatusKind {
+	//Damage per stack per second
+	pub fn dps(&self) -> f32 {
+		match self {
+			StatusKind::Burn => 6.,
+			StatusKind::Poison => 3.,
+			StatusKind::Shock => 4.,
+			StatusKind::Slow | StatusKind::Freeze => 0.,
+		}
+	}
+
+	//What its damage over time counts as
+	pub fn damage_kind(&self) -> DamageKind {
+		match self {
+			StatusKind::Poison => DamageKind::Toxic,
+			StatusKind::Burn | StatusKind::Shock | StatusKind::Slow | StatusKind::Freeze => DamageKind::Energy,
+		}
+	}
+
+	pub fn max_stacks(&self) -> u32 {
+		match self {
+			StatusKind::Burn => 5,
+			StatusKind::Poison => 20,
+			StatusKind::Slow => 4,
+			StatusKind::Freeze => 1,
+			StatusKind::Shock => 3,
+		}
+	}
+
+	//How far it jumps to other enemies when the one carrying it dies, 0 if it dies with them
+	pub fn spread_range(&self) -> f32 {
+		match self {
+			StatusKind::Burn => 60.,
+			StatusKind::Poison => 90.,
+			StatusKind::Shock => 120.,
+			StatusKind::Slow | StatusKind::Freeze => 0.,
+		}
+	}
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			StatusKind::Burn => "Burn",
+			StatusKind::Poison => "Poison",
+			StatusKind::Slow => "Slow",
+			StatusKind::Freeze => "Freeze",
+			StatusKind::Shock => "Shock",
+		}
+	}
+
+	pub fn tint(&self) -> LinearRgba {
+		match self {
+			StatusKind::Burn => LinearRgba::rgb(3.0, 0.8, 0.1),
+			StatusKind::Poison => LinearRgba::rgb(0.3, 2.0, 0.2),
+			StatusKind::Slow => LinearRgba::rgb(0.3, 0.8, 2.0),
+			StatusKind::Freeze => LinearRgba::rgb(1.5, 2.5, 3.0),
+			StatusKind::Shock => LinearRgba::rgb(2.5, 2.5, 0.4),
+		}
+	}
+}
+
+//What a hit applies, carried by projectile types, death scatters and the projectiles they spawn
+#[derive(Reflect, Deserialize, Clone, Copy, Debug)]
+pub struct StatusApplication {
+	pub kind: StatusKind,
+	pub stacks: u32,
+	pub duration: f32,
+}
+
+#[derive(Component, Reflect, Clone, Copy)]
+#[reflect(Component)]
+pub struct InflictStatus(pub StatusApplication);
+
+#[derive(Reflect, Clone, Debug)]
+pub struct ActiveStatus {
+	pub kind: StatusKind,
+	pub stacks: u32,
+	pub duration: Timer,
+	pub pulse: Timer,
+	//Side credited with its damage, whoever applied it last
+	pub instigator: Owner,
+}
+
+impl ActiveStatus {
+	//What is left of it, for passing it on
+	pub fn application(&self) -> StatusApplication {
+		StatusApplication {
+			kind: self.kind,
+			stacks: self.stacks,
+			duration: self.duration.remaining_secs(),
+		}
+	}
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct StatusEffects(pub Vec<ActiveStatus>);
+
+impl StatusEffects {
+	//Reapplying adds stacks up to the cap and keeps whichever duration lasts longer
+	pub fn apply(&mut self, application: StatusApplication, instigator: Owner) {
+		let kind = application.kind;
+		if let Some(active) = self.0.iter_mut().find(|active| active.kind == kind) {
+			active.stacks = (active.stacks + application.stacks).min(kind.max_stacks());
+			active.instigator = instigator;
+			if active.duration.remaining_secs() < application.duration {
+				active.duration = Timer::from_seconds(application.duration, TimerMode::Once);
+			}
+			return;
+		}
+		self.0.push(ActiveStatus {
+			kind,
+			stacks: application.stacks.min(kind.max_stacks()),
+			duration: Timer::from_seconds(application.duration, TimerMode::Once),
+			pulse: Timer::from_seconds(STATUS_PULSE, TimerMode::Repeating),
+			instigator,
+		});
+	}
+
+	pub fn speed_multiplier(&self) -> f32 {
+		return self
+			.0
+			.iter()
+			.map(|active| match active.kind {
+				StatusKind::Slow => (1. - 0.15 * active.stacks as f32).max(0.25),
+				StatusKind::Freeze => 0.,
+				_ => 1.,
+			})
+			.product();
+	}
+
+	pub fn is_stunned(&self) -> bool {
+		return self.0.iter().any(|active| match active.kind {
+			StatusKind::Freeze => true,
+			StatusKind::Shock => active.pulse.elapsed_secs() < SHOCK_STUN,
+			_ => false,
+		});
+	}
+
+	//The most recently applied effect decides the tint
+	pub fn tint(&self) -> Option<LinearRgba> {
+		return self.0.last().map(|active| active.kind.tint());
+	}
+}
@@ -3,10 +3,14 @@ use bevy_rapier2d::prelude::*;
 
 use crate::components::{utils::Cleanable, weapons::Weapon};
 
-use super::stats::{Health, MaxHealth};
+use super::{
+	stats::{Health, MaxHealth},
+	status::StatusEffects,
+};
 
-#[derive(Component, Default)]
-#[require(MaxHealth, Health, Transform, Visibility)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+#[require(MaxHealth, Health, StatusEffects, Transform, Visibility)]
 pub struct Enemy;
 
 #[derive(Component)]
@@ -45,7 +49,7 @@ impl From<Owner> for Projectile {
 	}
 }
 
-#[derive(Reflect, Default, Clone, Copy)]
+#[derive(Reflect, Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Owner {
 	#[default]
 	Player,
@@ -62,4 +66,5 @@ pub enum Pickup {
 	Health,
 	Weapon(Weapon),
 	Stats,
+	Xp(u32),
 }
@@ -4,6 +4,15 @@ use bevy::prelude::*;
 #[require(Transform)]
 pub struct HealthBar(pub Entity);
 
+//Fills up as the dash cooldown of the entity recovers
+#[derive(Component, Reflect)]
+#[require(Transform)]
+pub struct DashBar(pub Entity);
+
+//Part of the health bar across the top of the screen for a boss, removed once it dies
+#[derive(Component, Reflect)]
+pub struct BossBar(pub Entity);
+
 #[derive(Component, Reflect)]
 #[require(Text2d)]
 pub struct HealthBarText {
@@ -1,6 +1,10 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-#[derive(Component, Reflect, Default, Clone, Copy)]
+use super::{death::ScatterStage, status::StatusApplication, tags::Owner};
+
+#[derive(Component, Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[reflect(Component)]
 #[require(WeaponAuto, WeaponBeam, WeaponBurst, WeaponSpread, ProjectileType, WeaponFiring)]
 pub enum Weapon {
 	#[default]
@@ -10,6 +14,83 @@ pub enum Weapon {
 	Beam,
 }
 
+impl Weapon {
+	pub fn label(&self) -> &'static str {
+		match self {
+			Weapon::Auto => "Auto",
+			Weapon::Spread => "Spread",
+			Weapon::Burst => "Burst",
+			Weapon::Beam => "Beam",
+		}
+	}
+}
+
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct HeldWeapon {
+	pub weapon: Weapon,
+	pub level: u32,
+}
+
+//Weapons the player has picked up, the one in use is mirrored into `Weapon`
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct WeaponInventory {
+	//How many weapons can be held at once
+	pub slots: usize,
+	pub held: Vec<HeldWeapon>,
+	pub current: usize,
+}
+
+impl Default for WeaponInventory {
+	fn default() -> Self {
+		Self::new(3, Weapon::Auto)
+	}
+}
+
+impl WeaponInventory {
+	pub fn new(slots: usize, starting: Weapon) -> Self {
+		Self {
+			slots: slots.max(1),
+			held: vec![HeldWeapon {
+				weapon: starting,
+				level: 1,
+			}],
+			current: 0,
+		}
+	}
+
+	pub fn current(&self) -> Weapon {
+		self.held[self.current].weapon
+	}
+
+	//Levels up a weapon already held, otherwise takes a free slot or swaps out the one in use. Returns its level
+	pub fn add(&mut self, weapon: Weapon) -> u32 {
+		if let Some(held) = self.held.iter_mut().find(|held| held.weapon == weapon) {
+			held.level += 1;
+			return held.level;
+		}
+		let held = HeldWeapon { weapon, level: 1 };
+		if self.held.len() < self.slots {
+			self.held.push(held);
+			self.current = self.held.len() - 1;
+		} else {
+			self.held[self.current] = held;
+		}
+		return 1;
+	}
+
+	//Slot is 1 based, empty slots are ignored
+	pub fn select(&mut self, slot: u8) {
+		if (1..=self.held.len()).contains(&(slot as usize)) {
+			self.current = slot as usize - 1;
+		}
+	}
+
+	pub fn cycle(&mut self) {
+		self.current = (self.current + 1) % self.held.len();
+	}
+}
+
 #[derive(Component, Reflect, Default)]
 pub struct WeaponFiring(pub bool);
 
@@ -21,6 +102,7 @@ impl WeaponFiring {
 }
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct WeaponAuto {
 	pub damage_multi: f32,
 	pub speed_multi: f32,
@@ -30,6 +112,13 @@ pub struct WeaponAuto {
 	pub recoil: f32,
 }
 
+impl WeaponAuto {
+	pub fn upgrade(&mut self, rate: f32) {
+		let duration = self.fire_rate.duration().mul_f32(1. - rate);
+		self.fire_rate.set_duration(duration);
+	}
+}
+
 impl Default for WeaponAuto {
 	fn default() -> Self {
 		Self {
@@ -42,13 +131,8 @@ impl Default for WeaponAuto {
 	}
 }
 
-impl WeaponAuto {
-	pub fn upgrade(&mut self, rate: f32) {
-		self.speed_multi += self.speed_multi * rate;
-	}
-}
-
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct WeaponSpread {
 	pub damage_multi: f32,
 	pub speed_multi: f32,
@@ -73,14 +157,15 @@ impl Default for WeaponSpread {
 	}
 }
 
+//Speed and damage come from stat modifiers, upgrades only add to what the weapon does
 impl WeaponSpread {
-	pub fn upgrade(&mut self, rate: f32) {
-		self.speed_multi += self.speed_multi * rate;
+	pub fn upgrade(&mut self) {
 		self.shot_count += 1;
 	}
 }
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct WeaponBurst {
 	pub damage_multi: f32,
 	pub speed_multi: f32,
@@ -108,18 +193,20 @@ impl Default for WeaponBurst {
 }
 
 impl WeaponBurst {
-	pub fn upgrade(&mut self, rate: f32) {
-		self.speed_multi += self.speed_multi * rate;
+	pub fn upgrade(&mut self) {
 		self.burst += 1;
 	}
 }
 
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct WeaponBeam {
 	pub damage_multi: f32,
 	pub hit_rate: Timer,
 	pub max_range: f32,
 	pub recoil: f32,
+	//Distance to whatever the beam is currently hitting, up to max_range
+	pub length: f32,
 }
 
 impl Default for WeaponBeam {
@@ -129,28 +216,107 @@ impl Default for WeaponBeam {
 			hit_rate: Timer::from_seconds(1. / 5., TimerMode::Repeating),
 			max_range: 300.,
 			recoil: 40.,
+			length: 0.,
 		}
 	}
 }
 
+impl WeaponBeam {
+	pub fn upgrade(&mut self, rate: f32) {
+		self.max_range += self.max_range * rate;
+	}
+}
+
+#[derive(Component, Reflect)]
+pub struct BeamVisual;
+
+//Chance for the owner's projectiles to burst into a small explosion where they hit
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct ScatterOnHit {
+	pub chance: f32,
+	pub damage: f32,
+	pub range: f32,
+}
+
+//Steers the projectile toward the nearest target in range, picking a new one once the old one is gone
+#[derive(Component, Reflect, Clone, Copy)]
+pub struct Homing {
+	//Degrees per second
+	pub turn_rate: f32,
+	pub range: f32,
+	//Player owned ones go after enemies, enemy owned ones after the player
+	pub owner: Owner,
+	pub target: Option<Entity>,
+}
+
+impl Homing {
+	pub fn new(turn_rate: f32, range: f32, owner: Owner) -> Self {
+		return Self {
+			turn_rate,
+			range,
+			owner,
+			target: None,
+		};
+	}
+}
+
+//Breaks the projectile into smaller ones fanned out along its path whenever it hits something
+#[derive(Component, Reflect, Deserialize, Default, Clone, Copy, Debug)]
+pub struct Fragmentation {
+	pub count: u32,
+	//Degrees the fragments are spread across, 360 for a full ring
+	pub arc: f32,
+	//Fraction of the damage each fragment keeps
+	pub damage: f32,
+	//Fragments of fragments, so a cap of 1 only lets the original projectile split
+	pub generations: u32,
+}
+
+impl Fragmentation {
+	pub fn splits(&self, generation: u32) -> bool {
+		return self.count > 0 && generation < self.generations;
+	}
+}
+
+//Left by a fragmenting hit, it passes through whatever the projectile it came from struck
+#[derive(Component, Reflect, Clone, Copy)]
+pub struct Fragment {
+	pub ignore: Entity,
+	pub generation: u32,
+}
+
 #[derive(Component, Reflect, Clone, Copy)]
+#[reflect(Component)]
 pub enum ProjectileType {
 	Basic {
 		damage: f32,
 		speed: f32,
 		multishot: u32,
+		#[reflect(default)]
+		status: Option<StatusApplication>,
+		#[reflect(default)]
+		fragmentation: Fragmentation,
 	},
 	Piercing {
 		damage: f32,
 		speed: f32,
 		multishot: u32,
 		penetration: u32,
+		#[reflect(default)]
+		status: Option<StatusApplication>,
+		#[reflect(default)]
+		fragmentation: Fragmentation,
 	},
 	Bouncing {
 		damage: f32,
 		speed: f32,
 		multishot: u32,
 		bounce_limit: u32,
+		#[reflect(default)]
+		status: Option<StatusApplication>,
+		#[reflect(default)]
+		fragmentation: Fragmentation,
 	},
 	Grenade {
 		damage: f32,
@@ -161,6 +327,26 @@ pub enum ProjectileType {
 		drag: f32,
 		explosive_range: f32,
 		explosive_speed: f32,
+		#[reflect(default)]
+		status: Option<StatusApplication>,
+		//Extra stage set off alongside the explosion
+		#[reflect(default)]
+		payload: Option<ScatterStage>,
+		#[reflect(default)]
+		fragmentation: Fragmentation,
+	},
+	Homing {
+		damage: f32,
+		speed: f32,
+		multishot: u32,
+		//Degrees per second
+		turn_rate: f32,
+		//How far away a new target can be picked up from
+		range: f32,
+		#[reflect(default)]
+		status: Option<StatusApplication>,
+		#[reflect(default)]
+		fragmentation: Fragmentation,
 	},
 }
 
@@ -170,6 +356,8 @@ impl Default for ProjectileType {
 			damage: 40.,
 			speed: 200.,
 			multishot: 1,
+			status: None,
+			fragmentation: Fragmentation::default(),
 		}
 	}
 }
@@ -181,6 +369,61 @@ impl ProjectileType {
 			ProjectileType::Piercing { multishot, .. } => multishot,
 			ProjectileType::Bouncing { multishot, .. } => multishot,
 			ProjectileType::Grenade { multishot, .. } => multishot,
+			ProjectileType::Homing { multishot, .. } => multishot,
+		}
+	}
+	pub fn damage(&self) -> f32 {
+		*match self {
+			ProjectileType::Basic { damage, .. } => damage,
+			ProjectileType::Piercing { damage, .. } => damage,
+			ProjectileType::Bouncing { damage, .. } => damage,
+			ProjectileType::Grenade { damage, .. } => damage,
+			ProjectileType::Homing { damage, .. } => damage,
+		}
+	}
+	pub fn multishot_mut(&mut self) -> &mut u32 {
+		match self {
+			ProjectileType::Basic { multishot, .. } => multishot,
+			ProjectileType::Piercing { multishot, .. } => multishot,
+			ProjectileType::Bouncing { multishot, .. } => multishot,
+			ProjectileType::Grenade { multishot, .. } => multishot,
+			ProjectileType::Homing { multishot, .. } => multishot,
+		}
+	}
+	pub fn status_mut(&mut self) -> &mut Option<StatusApplication> {
+		match self {
+			ProjectileType::Basic { status, .. } => status,
+			ProjectileType::Piercing { status, .. } => status,
+			ProjectileType::Bouncing { status, .. } => status,
+			ProjectileType::Grenade { status, .. } => status,
+			ProjectileType::Homing { status, .. } => status,
+		}
+	}
+	pub fn status(&self) -> Option<StatusApplication> {
+		*match self {
+			ProjectileType::Basic { status, .. } => status,
+			ProjectileType::Piercing { status, .. } => status,
+			ProjectileType::Bouncing { status, .. } => status,
+			ProjectileType::Grenade { status, .. } => status,
+			ProjectileType::Homing { status, .. } => status,
+		}
+	}
+	pub fn fragmentation(&self) -> Fragmentation {
+		*match self {
+			ProjectileType::Basic { fragmentation, .. } => fragmentation,
+			ProjectileType::Piercing { fragmentation, .. } => fragmentation,
+			ProjectileType::Bouncing { fragmentation, .. } => fragmentation,
+			ProjectileType::Grenade { fragmentation, .. } => fragmentation,
+			ProjectileType::Homing { fragmentation, .. } => fragmentation,
+		}
+	}
+	pub fn fragmentation_mut(&mut self) -> &mut Fragmentation {
+		match self {
+			ProjectileType::Basic { fragmentation, .. } => fragmentation,
+			ProjectileType::Piercing { fragmentation, .. } => fragmentation,
+			ProjectileType::Bouncing { fragmentation, .. } => fragmentation,
+			ProjectileType::Grenade { fragmentation, .. } => fragmentation,
+			ProjectileType::Homing { fragmentation, .. } => fragmentation,
 		}
 	}
 	pub fn upgrade(&self, rate: f32) -> Self {
@@ -189,32 +432,44 @@ impl ProjectileType {
 				damage,
 				speed,
 				multishot,
+				status,
+				fragmentation,
 			} => ProjectileType::Basic {
 				damage: *damage,
-				speed: speed + speed * rate,
+				speed: *speed,
 				multishot: multishot + 1,
+				status: *status,
+				fragmentation: *fragmentation,
 			},
 			ProjectileType::Piercing {
 				damage,
 				speed,
 				multishot,
 				penetration,
+				status,
+				fragmentation,
 			} => ProjectileType::Piercing {
 				damage: *damage,
-				speed: speed + speed * rate,
+				speed: *speed,
 				multishot: multishot + 1,
 				penetration: penetration + 1,
+				status: *status,
+				fragmentation: *fragmentation,
 			},
 			ProjectileType::Bouncing {
 				damage,
 				speed,
 				multishot,
 				bounce_limit,
+				status,
+				fragmentation,
 			} => ProjectileType::Bouncing {
 				damage: *damage,
-				speed: speed + speed * rate,
+				speed: *speed,
 				multishot: multishot + 1,
 				bounce_limit: bounce_limit + 1,
+				status: *status,
+				fragmentation: *fragmentation,
 			},
 			ProjectileType::Grenade {
 				damage,
@@ -225,15 +480,38 @@ impl ProjectileType {
 				drag,
 				explosive_range,
 				explosive_speed,
+				status,
+				payload,
+				fragmentation,
 			} => ProjectileType::Grenade {
 				damage: *damage,
-				speed: speed + speed * rate,
+				speed: *speed,
 				multishot: multishot + 1,
 				bounce_limit: bounce_limit + 1,
 				fuse: (fuse - (fuse * rate)).max(1.),
 				explosive_range: explosive_range + explosive_range * rate,
 				explosive_speed: *explosive_speed,
 				drag: *drag,
+				status: *status,
+				payload: *payload,
+				fragmentation: *fragmentation,
+			},
+			ProjectileType::Homing {
+				damage,
+				speed,
+				multishot,
+				turn_rate,
+				range,
+				status,
+				fragmentation,
+			} => ProjectileType::Homing {
+				damage: *damage,
+				speed: *speed,
+				multishot: multishot + 1,
+				turn_rate: turn_rate + turn_rate * rate,
+				range: *range,
+				status: *status,
+				fragmentation: *fragmentation,
 			},
 		}
 	}
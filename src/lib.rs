@@ -0,0 +1,17 @@
+pub mod components;
+pub mod plugins;
+pub mod resources;
+pub mod state_management;
+
+use bevy_rapier2d::prelude::*;
+
+pub const NAME: &str = "Cataclyze";
+
+pub const AUDIO_SCALE: f32 = 1. / 100.0;
+
+pub const PLAYER_GROUP: Group = Group::from_bits_truncate(0b0001);
+pub const PLAYER_PROJECTILE_GROUP: Group = Group::from_bits_truncate(0b0010);
+pub const PLAYER_OWNED_GROUP: Group = Group::from_bits_truncate(0b0011);
+pub const ENEMY_GROUP: Group = Group::from_bits_truncate(0b0100);
+pub const ENEMY_PROJECTILE_GROUP: Group = Group::from_bits_truncate(0b1000);
+pub const ENEMY_OWNED_GROUP: Group = Group::from_bits_truncate(0b1100);
@@ -1,8 +1,3 @@
-mod components;
-mod plugins;
-mod resources;
-mod state_management;
-
 use bevy::asset::AssetMetaCheck;
 use bevy::audio::{AudioPlugin, SpatialScale};
 use bevy::prelude::*;
@@ -12,53 +7,105 @@ use bevy::window::PresentMode;
 use bevy_inspector_egui::bevy_egui::EguiPlugin;
 #[cfg(feature = "inspect")]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use bevy_rapier2d::prelude::*;
-use plugins::GamePlugin;
-
-const NAME: &str = "Cataclyze";
-
-const AUDIO_SCALE: f32 = 1. / 100.0;
+#[cfg(feature = "phys")]
+use bevy_rapier2d::render::RapierDebugRenderPlugin;
+use cataclyze::{
+	AUDIO_SCALE, NAME,
+	plugins::GamePlugin,
+	resources::{
+		input::InputSource,
+		replay::{Replay, ReplayPlayback, ReplayRecorder},
+		utils::RunSeed,
+	},
+};
+#[cfg(feature = "inspect")]
+use iyes_perf_ui::{
+	PerfUiPlugin,
+	prelude::{PerfUiEntryFPS, PerfUiEntryFrameTimeWorst, PerfUiEntryRenderGpuTime},
+};
 
-const PLAYER_GROUP: Group = Group::from_bits_truncate(0b0001);
-const PLAYER_PROJECTILE_GROUP: Group = Group::from_bits_truncate(0b0010);
-const PLAYER_OWNED_GROUP: Group = Group::from_bits_truncate(0b0011);
-const ENEMY_GROUP: Group = Group::from_bits_truncate(0b0100);
-const ENEMY_PROJECTILE_GROUP: Group = Group::from_bits_truncate(0b1000);
-#[allow(dead_code)]
-const ENEMY_OWNED_GROUP: Group = Group::from_bits_truncate(0b1100);
 fn main() {
-	App::new()
-		.add_plugins((
-			DefaultPlugins
-				.set(AssetPlugin {
-					meta_check: AssetMetaCheck::Never,
-					..default()
-				})
-				.set(WindowPlugin {
-					primary_window: Some(Window {
-						title: NAME.into(),
-						name: Some(NAME.into()),
-						resolution: (1920., 1080.).into(),
-						#[cfg(debug_assertions)]
-						present_mode: PresentMode::AutoNoVsync,
-						..default()
-					}),
-					..default()
-				})
-				.set(AudioPlugin {
-					default_spatial_scale: SpatialScale::new_2d(AUDIO_SCALE),
+	let mut app = App::new();
+	if let Some(seed) = arg_value("--seed").and_then(|seed| seed.parse().ok()) {
+		app.insert_resource(RunSeed::fixed(seed));
+	}
+	if let Some(path) = arg_value("--record") {
+		app.insert_resource(ReplayRecorder {
+			path: Some(path),
+			..default()
+		});
+	}
+	app.add_plugins((
+		DefaultPlugins
+			.set(AssetPlugin {
+				meta_check: AssetMetaCheck::Never,
+				..default()
+			})
+			.set(WindowPlugin {
+				primary_window: Some(Window {
+					title: NAME.into(),
+					name: Some(NAME.into()),
+					resolution: (1920., 1080.).into(),
+					#[cfg(debug_assertions)]
+					present_mode: PresentMode::AutoNoVsync,
 					..default()
 				}),
-			RapierPhysicsPlugin::<NoUserData>::default(),
-			GamePlugin,
-			#[cfg(feature = "inspect")]
-			EguiPlugin {
-				enable_multipass_for_primary_context: true,
-			},
-			#[cfg(feature = "inspect")]
-			WorldInspectorPlugin::new(),
-			#[cfg(feature = "phys")]
-			RapierDebugRenderPlugin::default(),
-		))
-		.run();
+				..default()
+			})
+			.set(AudioPlugin {
+				default_spatial_scale: SpatialScale::new_2d(AUDIO_SCALE),
+				..default()
+			}),
+		GamePlugin,
+		#[cfg(feature = "inspect")]
+		EguiPlugin {
+			enable_multipass_for_primary_context: true,
+		},
+		#[cfg(feature = "inspect")]
+		WorldInspectorPlugin::new(),
+		#[cfg(feature = "inspect")]
+		(
+			bevy::diagnostic::FrameTimeDiagnosticsPlugin::default(),
+			// bevy::diagnostic::EntityCountDiagnosticsPlugin,
+			bevy::diagnostic::SystemInformationDiagnosticsPlugin,
+			bevy::render::diagnostic::RenderDiagnosticsPlugin,
+			PerfUiPlugin,
+		),
+		#[cfg(feature = "phys")]
+		RapierDebugRenderPlugin::default(),
+	));
+	//Loaded once logging is up so a bad replay is reported like any other error
+	if let Some(path) = arg_value("--replay") {
+		match Replay::load(&path) {
+			Ok(replay) => {
+				app.insert_resource(RunSeed::fixed(replay.seed));
+				app.insert_resource(ReplayPlayback::new(replay));
+				app.insert_resource(InputSource::Replay);
+			}
+			Err(err) => error!("Failed to load replay {path}: {err}"),
+		}
+	}
+	#[cfg(feature = "inspect")]
+	app.add_systems(Startup, spawn_perf_ui);
+	app.run();
+}
+
+//`--seed <n>` plays a specific run, `--record <path>` picks where the replay is saved and `--replay <path>` plays one back
+fn arg_value(name: &str) -> Option<String> {
+	let mut args = std::env::args();
+	while let Some(arg) = args.next() {
+		if arg == name {
+			return args.next();
+		}
+	}
+	return None;
+}
+
+#[cfg(feature = "inspect")]
+fn spawn_perf_ui(mut commands: Commands) {
+	commands.spawn((
+		PerfUiEntryFPS::default(),
+		PerfUiEntryRenderGpuTime::default(),
+		PerfUiEntryFrameTimeWorst::default(),
+	));
 }
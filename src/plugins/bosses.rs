@@ -0,0 +1,124 @@
+use bevy::{ecs::entity_disabling::Disabled, prelude::*};
+
+use crate::{
+	components::{
+		ai::{ChargeAI, ChargeInfo, HoverAI},
+		boss::{Boss, BossAttack, BossState},
+		death::ChainLink,
+		stats::{Health, Life, MaxHealth, MoveSpeedMultiplier},
+		utils::Cleanable,
+	},
+	plugins::utils::play_audio_onshot,
+	resources::{audio::AudioClips, enemies::EnemyPrefabs, utils::RandomGen},
+	state_management::{GameplaySystems, RngSystems},
+};
+
+const SUMMON_RANGE: f32 = 60.;
+
+pub struct BossesPlugin;
+
+impl Plugin for BossesPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(
+			FixedUpdate,
+			(boss_phases, boss_attacks)
+				.chain()
+				.in_set(GameplaySystems)
+				.in_set(RngSystems::Bosses),
+		);
+	}
+}
+
+//Movement is swapped out with the phase, the AI systems then drive whichever one is present
+fn boss_phases(
+	query: Query<(Entity, &Boss, &mut BossState, &Health, &MaxHealth, &Life)>,
+	audio: Res<AudioClips>,
+	mut commands: Commands,
+) {
+	for (entity, boss, mut state, health, max_health, life) in query {
+		if life.is_dead() {
+			continue;
+		}
+		let phase = boss.phase_at(health.0 / max_health.0);
+		if state.phase == Some(phase) {
+			continue;
+		}
+		let Some(definition) = boss.phases.get(phase) else {
+			continue;
+		};
+		if state.phase.is_some() {
+			play_audio_onshot(&mut commands, audio.pickup.clone());
+		}
+		state.phase = Some(phase);
+		state.timers = definition.attacks.iter().map(BossAttack::timer).collect();
+		let charge = definition.attacks.iter().find_map(|attack| match attack {
+			BossAttack::Charge(charge) => Some(charge.clone()),
+			_ => None,
+		});
+		let mut entity_commands = commands.entity(entity);
+		entity_commands.insert(MoveSpeedMultiplier(1.));
+		match charge {
+			Some(charge) => {
+				entity_commands
+					.remove::<HoverAI>()
+					.insert((charge, ChargeInfo::default()));
+			}
+			None => {
+				entity_commands
+					.remove::<(ChargeAI, ChargeInfo)>()
+					.insert(boss.hover.clone());
+			}
+		}
+	}
+}
+
+fn boss_attacks(
+	query: Query<(&Boss, &mut BossState, &Transform, &Life)>,
+	prefabs: Res<EnemyPrefabs>,
+	time: Res<Time>,
+	mut rng: ResMut<RandomGen>,
+	mut commands: Commands,
+) {
+	for (boss, mut state, transform, life) in query {
+		if life.is_dead() {
+			continue;
+		}
+		let Some(phase) = state.phase.and_then(|phase| boss.phases.get(phase)) else {
+			continue;
+		};
+		for (attack, timer) in phase.attacks.iter().zip(state.timers.iter_mut()) {
+			timer.tick(time.delta());
+			for _ in 0..timer.times_finished_this_tick() {
+				match attack {
+					//Left for the death scatter systems to set off, like a hit scatter
+					BossAttack::Barrage { scatter, .. } => {
+						commands.spawn((
+							Name::new("Boss Barrage"),
+							scatter.clone(),
+							Life(false, true),
+							ChainLink::ENEMY,
+							Transform::from_translation(transform.translation).with_rotation(transform.rotation),
+							Cleanable,
+						));
+					}
+					BossAttack::Summon { archetype, count, .. } => {
+						let Some(prefab) = prefabs.0.get(archetype) else {
+							warn!("No enemy prefab for archetype {}", archetype);
+							continue;
+						};
+						for _ in 0..*count {
+							let pos = transform.translation + rng.point_on_unit_circle().extend(0.0) * SUMMON_RANGE;
+							commands
+								.entity(*prefab)
+								.clone_and_spawn_with(|builder| {
+									builder.deny::<Disabled>();
+								})
+								.insert(Transform::from_translation(pos));
+						}
+					}
+					BossAttack::Charge(_) => {}
+				}
+			}
+		}
+	}
+}
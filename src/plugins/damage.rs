@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::{
+	components::{
+		death::ChainLink,
+		effects::HitFlash,
+		elite::Shield,
+		stats::{Armor, Health, IFrames, Life, Resistances},
+		tags::Owner,
+	},
+	plugins::{player::Player, utils::play_audio_onshot},
+	resources::{
+		audio::AudioClips,
+		effects::HitStop,
+		utils::{DamageEvent, DamageKind, RandomGen},
+	},
+	state_management::{GameOverSystems, GameplaySystems, RngSystems},
+};
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<DamageEvent>();
+		app.add_systems(
+			FixedPostUpdate,
+			process_damage.in_set(GameplaySystems).in_set(RngSystems::Damage),
+		);
+		app.add_systems(
+			FixedPostUpdate,
+			process_damage.in_set(GameOverSystems).in_set(RngSystems::Damage),
+		);
+	}
+}
+
+fn process_damage(
+	mut events: EventReader<DamageEvent>,
+	mut targets: Query<(
+		&mut Health,
+		&mut Life,
+		&Transform,
+		Option<&mut Velocity>,
+		Option<&mut IFrames>,
+		Option<&Resistances>,
+		Option<&Armor>,
+		Option<&Player>,
+		Option<&mut Shield>,
+	)>,
+	links: Query<&ChainLink>,
+	mut commands: Commands,
+	mut rng: ResMut<RandomGen>,
+	mut hit_stop: ResMut<HitStop>,
+	audio: Res<AudioClips>,
+) {
+	for event in events.read() {
+		if let Ok((mut health, mut life, transform, vel, iframes, resistances, armor, player, shield)) =
+			targets.get_mut(event.target)
+		{
+			//Harmless contacts like a grenade bouncing off shouldn't use up i-frames or knock anything back
+			if life.is_dead() || event.amount <= 0. {
+				continue;
+			}
+			//Also stops hits landing in the same tick from stacking up
+			if let Some(mut iframes) = iframes {
+				if iframes.is_active() {
+					continue;
+				}
+				iframes.hit();
+			}
+			play_sounds(&audio, &mut commands, player.is_some(), event.instigator);
+			if let Some(mut vel) = vel {
+				let away = (transform.translation.xy() - event.origin).normalize_or_zero();
+				vel.linvel += away * event.knockback;
+			}
+			commands.entity(event.target).try_insert(HitFlash::default());
+			if player.is_some() {
+				hit_stop.trigger();
+			}
+			//Only hits that can crit draw from the run's rng
+			let crit = event.crit.chance > 0. && rng.chance(event.crit.chance);
+			let multiplier = if crit { event.crit.multiplier } else { 1. };
+			let mut amount = final_damage(event.amount * multiplier, event.kind, resistances, armor);
+			if let Some(mut shield) = shield {
+				amount = shield.absorb(amount);
+			}
+			apply_damage(&mut health, &mut life, amount);
+			//Kills by chain reaction products carry the chain on
+			if life.is_dead()
+				&& let Ok(link) = links.get(event.source)
+			{
+				commands.entity(event.target).insert(*link);
+			}
+		}
+	}
+}
+
+pub fn play_sounds(audio: &AudioClips, commands: &mut Commands, is_player: bool, owner: Owner) {
+	if let Owner::Enemy = owner
+		&& !is_player
+	{
+		return;
+	}
+	let clip = if is_player {
+		audio.hurt.clone()
+	} else {
+		audio.hit.clone()
+	};
+	play_audio_onshot(commands, clip);
+}
+
+//The one place damage is mitigated, resistances scale the hit and armor takes a flat amount off what is left
+pub fn final_damage(amount: f32, kind: DamageKind, resistances: Option<&Resistances>, armor: Option<&Armor>) -> f32 {
+	//Armor can blunt a hit but never cancel it out entirely
+	const MIN_DAMAGE: f32 = 0.1;
+	let resisted = amount * (1. - resistances.map_or(0., |resistances| resistances.get(kind)));
+	return (resisted - armor.map_or(0., |armor| armor.0)).max(resisted * MIN_DAMAGE);
+}
+
+pub fn apply_damage(health: &mut Health, life: &mut Life, amount: f32) {
+	health.0 -= amount;
+	if health.0 <= 0. {
+		life.0 = false;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn unmitigated_hits_land_in_full() {
+		assert_eq!(final_damage(10., DamageKind::Kinetic, None, None), 10.);
+	}
+
+	#[test]
+	fn resistances_scale_before_armor() {
+		let resistances = Resistances {
+			explosive: 0.5,
+			energy: -0.5,
+			..default()
+		};
+		let armor = Armor(2.);
+		assert_eq!(
+			final_damage(10., DamageKind::Explosive, Some(&resistances), Some(&armor)),
+			3.
+		);
+		//Weaknesses take extra damage
+		assert_eq!(final_damage(10., DamageKind::Energy, Some(&resistances), None), 15.);
+		assert_eq!(
+			final_damage(10., DamageKind::Kinetic, Some(&resistances), Some(&armor)),
+			8.
+		);
+	}
+
+	#[test]
+	fn armor_never_cancels_a_hit() {
+		let damage = final_damage(5., DamageKind::Contact, None, Some(&Armor(100.)));
+		assert!((damage - 0.5).abs() < 1e-6, "Dealt {damage}");
+	}
+}
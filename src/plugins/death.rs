@@ -1,23 +1,30 @@
 use std::f32::consts::PI;
 
-use bevy::{audio::Volume, prelude::*};
+use bevy::{audio::Volume, ecs::system::SystemParam, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-	ENEMY_PROJECTILE_GROUP,
+	ENEMY_PROJECTILE_GROUP, PLAYER_OWNED_GROUP, PLAYER_PROJECTILE_GROUP,
 	components::{
-		death::{DeathScatter, ScatterPattern, SpiralSpawner, Targeting},
+		archetype::EnemyArchetype,
+		death::{
+			ChainArc, ChainLink, DeathScatter, Mine, ScatterOwner, ScatterPattern, ScatterProgress, ScatterStage,
+			SpiralSpawner, StageProgress, Targeting,
+		},
 		effects::Explosion,
-		stats::{Damage, Life},
-		tags::Projectile,
-		utils::Lifetime,
+		elite::Elite,
+		stats::{Critical, Damage, Knockback, Life},
+		status::{InflictStatus, StatusEffects},
+		tags::{Enemy, Owner, Projectile},
+		utils::{Cleanable, Lifetime},
+		weapons::Homing,
 	},
 	plugins::utils::play_audio_onshot,
 	resources::{
 		audio::AudioClips,
-		utils::{DeathEvent, KillCount, RandomGen},
+		utils::{ChainStats, DamageEvent, DamageKind, DeathEvent, KillCount, RandomGen},
 	},
-	state_management::{GameOverSystems, GameplaySystems},
+	state_management::{GameOverSystems, GameStartSystems, GameplaySystems, RngSystems},
 };
 
 use super::player::Player;
@@ -27,24 +34,49 @@ pub struct DeathPlugin;
 impl Plugin for DeathPlugin {
 	fn build(&self, app: &mut App) {
 		app.init_resource::<KillCount>();
+		app.init_resource::<ChainStats>();
+		app.register_type::<ChainStats>();
 		app.add_event::<DeathEvent>();
 		app.add_systems(Startup, init_meshes);
 		app.add_systems(
-			Update,
-			(death_events, death_scatter, sprial_spawner)
+			FixedUpdate,
+			(death_events, death_scatter, scatter_stages, sprial_spawner)
 				.chain()
-				.in_set(GameplaySystems),
+				.in_set(GameplaySystems)
+				.in_set(RngSystems::Deaths),
+		);
+		app.add_systems(FixedUpdate, expire_chains.in_set(GameplaySystems));
+		app.add_systems(Update, reset_stats.in_set(GameStartSystems));
+		app.add_systems(
+			FixedPostUpdate,
+			(death_scatter, scatter_stages, sprial_spawner)
+				.chain()
+				.in_set(GameOverSystems)
+				.in_set(RngSystems::Deaths),
 		);
-		app.add_systems(PostUpdate, (death_scatter, sprial_spawner).in_set(GameOverSystems));
 	}
 }
 
-fn death_events(
-	query: Query<(&mut Life, &Transform, Option<&Player>)>,
+pub fn death_events(
+	query: Query<
+		(
+			Entity,
+			&mut Life,
+			&Transform,
+			Option<&Player>,
+			Option<&ChainLink>,
+			Option<&EnemyArchetype>,
+			Option<&Elite>,
+		),
+		Or<(With<Enemy>, With<Player>)>,
+	>,
 	mut events: EventWriter<DeathEvent>,
 	mut kill_count: ResMut<KillCount>,
+	mut chains: ResMut<ChainStats>,
+	time: Res<Time>,
+	mut commands: Commands,
 ) {
-	for (mut life, transform, player) in query {
+	for (entity, mut life, transform, player, link, archetype, elite) in query {
 		if life.is_alive() || life.1 {
 			continue;
 		}
@@ -52,109 +84,397 @@ fn death_events(
 		events.write(DeathEvent {
 			pos: transform.translation.xy(),
 			is_player: player.is_some(),
+			archetype: archetype.map(|archetype| archetype.0.clone()),
+			affixes: elite.map(|elite| elite.0.clone()).unwrap_or_default(),
 		});
 		if player.is_none() {
 			kill_count.0 += 1;
+			//Deaths not caused by a chain reaction start a new one
+			let link = match link {
+				Some(link) => *link,
+				None => {
+					let link = chains.start_chain();
+					commands.entity(entity).insert(link);
+					link
+				}
+			};
+			chains.record_kill(link, time.elapsed_secs());
 		}
 	}
 }
 
+fn expire_chains(mut chains: ResMut<ChainStats>, time: Res<Time>) {
+	chains.expire(time.elapsed_secs());
+}
+
+fn reset_stats(mut kill_count: ResMut<KillCount>, mut chains: ResMut<ChainStats>) {
+	kill_count.0 = 0;
+	*chains = ChainStats::default();
+}
+
+const HOMING_SPEED: f32 = 150.;
+const MINE_BLAST_SPEED: f32 = 3.;
+const ARC_WIDTH: f32 = 2.;
+const ARC_LIFETIME: f32 = 0.15;
+
 #[derive(Resource, Reflect, Default)]
 struct Projectiles {
 	mesh: Handle<Mesh>,
 	mat: Handle<ColorMaterial>,
+	mine_mesh: Handle<Mesh>,
+	mine_mat: Handle<ColorMaterial>,
+	arc_mesh: Handle<Mesh>,
+	arc_mat: Handle<ColorMaterial>,
 }
 
 fn init_meshes(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>) {
 	commands.insert_resource(Projectiles {
 		mesh: meshes.add(Circle::new(2.)),
 		mat: materials.add(Color::linear_rgb(3.0, 0.0, 0.48)),
+		mine_mesh: meshes.add(RegularPolygon::new(4., 6)),
+		mine_mat: materials.add(Color::linear_rgb(3.0, 1.2, 0.1)),
+		arc_mesh: meshes.add(Rectangle::new(1., 1.)),
+		arc_mat: materials.add(Color::linear_rgb(1.0, 2.0, 4.0)),
 	});
 }
 
+//Dead entities start working through their stages, joining the chain that killed them
 fn death_scatter(
-	query: Query<(&Transform, &DeathScatter, &Life, Entity)>,
-	player: Single<&Transform, With<Player>>,
+	query: Query<(Entity, &DeathScatter, &Life, Option<&ChainLink>), Without<ScatterProgress>>,
+	mut chains: ResMut<ChainStats>,
 	mut commands: Commands,
-	mesh_data: Res<Projectiles>,
-	mut rng: ResMut<RandomGen>,
-	audio: Res<AudioClips>,
 ) {
-	for (transform, scatter, life, entity) in query {
+	for (entity, scatter, life, link) in query {
 		if life.is_alive() {
 			continue;
 		}
+		let chain = match link {
+			Some(link) => link.next(),
+			None => chains.start_chain(),
+		};
+		commands.entity(entity).insert(ScatterProgress {
+			chain,
+			stages: scatter.stages.iter().map(StageProgress::new).collect(),
+		});
+	}
+}
+
+//Everything a stage might need to go off
+#[derive(SystemParam)]
+struct ScatterContext<'w, 's> {
+	commands: Commands<'w, 's>,
+	mesh_data: Res<'w, Projectiles>,
+	rng: ResMut<'w, RandomGen>,
+	audio: Res<'w, AudioClips>,
+	targets: Query<
+		'w,
+		's,
+		(
+			Entity,
+			&'static Transform,
+			&'static Life,
+			Option<&'static mut StatusEffects>,
+			Has<Player>,
+		),
+		Or<(With<Enemy>, With<Player>)>,
+	>,
+	damage_events: EventWriter<'w, DamageEvent>,
+}
+
+fn scatter_stages(
+	query: Query<(
+		Entity,
+		&Transform,
+		&DeathScatter,
+		&mut ScatterProgress,
+		Option<&Critical>,
+		Option<&Children>,
+		Option<&Projectile>,
+		Option<&ScatterOwner>,
+	)>,
+	spirals: Query<(), With<SpiralSpawner>>,
+	player: Single<&Transform, With<Player>>,
+	time: Res<Time>,
+	mut context: ScatterContext,
+) {
+	for (entity, transform, scatter, mut progress, crit, children, projectile, scatter_owner) in query {
+		let chain = progress.chain;
+		//Scatters carried by the player's projectiles stay on the player's side
+		let owner = projectile
+			.map(|projectile| projectile.0)
+			.or(scatter_owner.map(|scatter_owner| scatter_owner.0))
+			.unwrap_or(Owner::Enemy);
+		for (stage, stage_progress) in scatter.stages.iter().zip(progress.stages.iter_mut()) {
+			if stage_progress.remaining == 0 {
+				continue;
+			}
+			stage_progress.timer.tick(time.delta());
+			if stage_progress.timer.finished() {
+				stage_progress.remaining -= 1;
+				stage_progress.timer = Timer::from_seconds(stage.interval, TimerMode::Once);
+				fire_stage(
+					&mut context,
+					entity,
+					stage,
+					transform,
+					player.translation,
+					chain,
+					crit,
+					owner,
+				);
+			}
+		}
+		//Spirals keep going on their own after the stage that started them
+		let spiralling = children.is_some_and(|children| children.iter().any(|child| spirals.contains(child)));
+		if progress.is_finished() && !spiralling {
+			context.commands.entity(entity).despawn();
+		}
+	}
+}
 
-		match scatter.pattern {
-			ScatterPattern::Explosion { range, speed } => {
-				commands.spawn((
+fn fire_stage(
+	context: &mut ScatterContext,
+	entity: Entity,
+	stage: &ScatterStage,
+	transform: &Transform,
+	player: Vec3,
+	chain: ChainLink,
+	crit: Option<&Critical>,
+	owner: Owner,
+) {
+	let commands = &mut context.commands;
+	let mesh_data = &context.mesh_data;
+	match stage.pattern {
+		ScatterPattern::Explosion { range, speed } => {
+			let explosion = commands
+				.spawn((
 					Explosion {
 						range,
 						epansion_rate: speed,
+						owner,
 					},
-					Damage(scatter.damage),
+					Damage(stage.damage),
+					Knockback(stage.knockback),
+					chain,
 					Transform::from_translation(transform.translation).with_scale(Vec3::splat(0.01)),
 					ActiveEvents::COLLISION_EVENTS,
-					CollisionGroups::new(ENEMY_PROJECTILE_GROUP, Group::ALL),
+					explosion_groups(owner),
 					Collider::ball(1.),
-				));
-				play_audio_onshot(&mut commands, audio.explosion.clone());
-				commands.entity(entity).despawn();
+				))
+				.id();
+			if let Some(status) = stage.status {
+				commands.entity(explosion).insert(InflictStatus(status));
 			}
-			ScatterPattern::Spread { arc, targeting } => {
-				let aim = match targeting {
-					Targeting::Forward => transform.up().as_vec3(),
-					Targeting::Random => {
-						let angle = rng.range(-PI..PI);
-						Vec3::new(angle.cos(), angle.sin(), 0.0)
-					}
-					Targeting::Player => (player.translation - transform.translation).normalize_or(Vec3::Y),
-				};
-				let interval = arc / scatter.count as f32;
-				let mesh = mesh_data.mesh.clone();
-				let mat = mesh_data.mat.clone();
-				let dmg = scatter.damage;
-				let base_pos = transform.translation.xy();
-				let bulk = (0..scatter.count).map(move |i| {
-					let angle = (i as f32 * interval) - arc / 2.;
-					let dir = (Quat::from_axis_angle(Vec3::Z, angle.to_radians()) * aim).xy();
-					return get_projectile(base_pos + dir * 20., dir * 200., dmg, mesh.clone(), mat.clone());
-				});
-				commands.spawn_batch(bulk);
-				commands.entity(entity).despawn();
+			if let Some(crit) = crit {
+				commands.entity(explosion).insert(*crit);
+			}
+			play_audio_onshot(commands, context.audio.explosion.clone());
+		}
+		ScatterPattern::Spread { arc, targeting } => {
+			let aim = match targeting {
+				Targeting::Forward => transform.up().as_vec3(),
+				Targeting::Random => {
+					let angle = context.rng.range(-PI..PI);
+					Vec3::new(angle.cos(), angle.sin(), 0.0)
+				}
+				Targeting::Player => (player - transform.translation).normalize_or(Vec3::Y),
+			};
+			let interval = arc / stage.count as f32;
+			let mesh = mesh_data.mesh.clone();
+			let mat = mesh_data.mat.clone();
+			let dmg = stage.damage;
+			let knockback = stage.knockback;
+			let base_pos = transform.translation.xy();
+			let bulk = (0..stage.count).map(move |i| {
+				let angle = (i as f32 * interval) - arc / 2.;
+				let dir = (Quat::from_axis_angle(Vec3::Z, angle.to_radians()) * aim).xy();
+				return (
+					get_projectile(
+						base_pos + dir * 20.,
+						dir * 200.,
+						dmg,
+						knockback,
+						owner,
+						mesh.clone(),
+						mat.clone(),
+					),
+					chain,
+				);
+			});
+			match stage.status {
+				Some(status) => commands.spawn_batch(bulk.map(move |proj| (proj, InflictStatus(status)))),
+				None => commands.spawn_batch(bulk),
+			}
+		}
+		ScatterPattern::Spiral { angle, rate } => {
+			commands.entity(entity).with_child((
+				Name::new("Spiral"),
+				Transform::IDENTITY,
+				SpiralSpawner {
+					angle,
+					timer: Timer::from_seconds(1.0 / rate, TimerMode::Repeating),
+					count: stage.count,
+					damage: stage.damage,
+					status: stage.status,
+					knockback: stage.knockback,
+					chain,
+					owner,
+					mesh: mesh_data.mesh.clone(),
+					material: mesh_data.mat.clone(),
+					..default()
+				},
+			));
+		}
+		ScatterPattern::Ring { offset } => {
+			let interval = 360. / stage.count as f32;
+			let base_pos = transform.translation.xy();
+			for i in 0..stage.count {
+				let dir = Vec2::from_angle((offset + i as f32 * interval).to_radians());
+				let proj = commands
+					.spawn((
+						get_projectile(
+							base_pos + dir * 20.,
+							dir * 200.,
+							stage.damage,
+							stage.knockback,
+							owner,
+							mesh_data.mesh.clone(),
+							mesh_data.mat.clone(),
+						),
+						chain,
+					))
+					.id();
+				if let Some(status) = stage.status {
+					commands.entity(proj).insert(InflictStatus(status));
+				}
+			}
+		}
+		//Flung out evenly and left to find their own way
+		ScatterPattern::Homing { turn_rate, range } => {
+			let interval = 360. / stage.count as f32;
+			let start = context.rng.range(0.0..360.0f32);
+			let base_pos = transform.translation.xy();
+			for i in 0..stage.count {
+				let dir = Vec2::from_angle((start + i as f32 * interval).to_radians());
+				let proj = commands
+					.spawn((
+						get_projectile(
+							base_pos + dir * 20.,
+							dir * HOMING_SPEED,
+							stage.damage,
+							stage.knockback,
+							owner,
+							mesh_data.mesh.clone(),
+							mesh_data.mat.clone(),
+						),
+						Homing::new(turn_rate, range, owner),
+						chain,
+					))
+					.id();
+				if let Some(status) = stage.status {
+					commands.entity(proj).insert(InflictStatus(status));
+				}
 			}
-			ScatterPattern::Spiral { angle, rate } => {
-				commands
-					.entity(entity)
-					.with_child((
-						Name::new("Spiral"),
-						Transform::IDENTITY,
-						SpiralSpawner {
-							angle,
-							timer: Timer::from_seconds(1.0 / rate, TimerMode::Repeating),
-							count: scatter.count,
-							damage: scatter.damage,
-							mesh: mesh_data.mesh.clone(),
-							material: mesh_data.mat.clone(),
+		}
+		ScatterPattern::Mines { radius, fuse, range } => {
+			for _ in 0..stage.count {
+				let pos = transform.translation.xy() + context.rng.point_on_circle_vec2(radius);
+				let mine = commands
+					.spawn((
+						Name::new("Mine"),
+						Mine,
+						DeathScatter::from(ScatterStage {
+							count: 1,
+							pattern: ScatterPattern::Explosion {
+								range,
+								speed: range * MINE_BLAST_SPEED,
+							},
+							damage: stage.damage,
+							status: stage.status,
+							knockback: stage.knockback,
 							..default()
-						},
+						}),
+						Lifetime::new(fuse),
+						chain,
+						ScatterOwner(owner),
+						Mesh2d(mesh_data.mine_mesh.clone()),
+						MeshMaterial2d(mesh_data.mine_mat.clone()),
+						Transform::from_translation(pos.extend(0.)),
+						Cleanable,
 					))
-					.remove::<DeathScatter>();
+					.id();
+				if let Some(crit) = crit {
+					commands.entity(mine).insert(*crit);
+				}
 			}
-		};
-	}
+		}
+		ScatterPattern::Chain { range, falloff } => {
+			let range_sq = range * range;
+			let mut hit = vec![entity];
+			let mut from = transform.translation.xy();
+			let mut damage = stage.damage;
+			for _ in 0..stage.count {
+				let next = context
+					.targets
+					.iter()
+					.filter(|(target, _, life, _, is_player)| {
+						life.is_alive() && !hit.contains(target) && !(*is_player && matches!(owner, Owner::Player))
+					})
+					.map(|(target, target_transform, ..)| (target, target_transform.translation.xy()))
+					.filter(|(_, pos)| pos.distance_squared(from) <= range_sq)
+					.min_by(|(_, a), (_, b)| a.distance_squared(from).total_cmp(&b.distance_squared(from)));
+				let Some((target, pos)) = next else {
+					break;
+				};
+				//The arc carries the chain so kills it makes join it
+				let length = from.distance(pos);
+				let arc = commands
+					.spawn((
+						Name::new("Chain Arc"),
+						ChainArc,
+						chain,
+						Lifetime::new(ARC_LIFETIME),
+						Mesh2d(mesh_data.arc_mesh.clone()),
+						MeshMaterial2d(mesh_data.arc_mat.clone()),
+						Transform::from_translation(((from + pos) / 2.).extend(0.))
+							.with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, (pos - from).normalize_or(Vec2::Y)))
+							.with_scale(Vec3::new(ARC_WIDTH, length, 1.)),
+						Cleanable,
+					))
+					.id();
+				context.damage_events.write(DamageEvent {
+					source: arc,
+					target,
+					amount: damage,
+					kind: DamageKind::Energy,
+					crit: crit.copied().unwrap_or_default(),
+					origin: from,
+					knockback: stage.knockback,
+					instigator: owner,
+				});
+				if let Some(status) = stage.status
+					&& let Ok((.., Some(mut statuses), _)) = context.targets.get_mut(target)
+				{
+					statuses.apply(status, owner);
+				}
+				hit.push(target);
+				from = pos;
+				damage *= 1. - falloff;
+			}
+		}
+	};
 }
 
 fn sprial_spawner(
-	mut query: Query<(&GlobalTransform, &mut SpiralSpawner, &ChildOf)>,
+	mut query: Query<(Entity, &GlobalTransform, &mut SpiralSpawner)>,
 	time: Res<Time>,
 	mut commands: Commands,
 	audio: Res<AudioClips>,
 ) {
-	for (transform, mut spiral, parent) in &mut query {
+	for (entity, transform, mut spiral) in &mut query {
 		if spiral.spawn_count >= spiral.count {
-			commands.entity(parent.0).despawn();
-			return;
+			commands.entity(entity).despawn();
+			continue;
 		}
 		spiral.timer.tick(time.delta());
 		if spiral.timer.finished() {
@@ -165,17 +485,25 @@ fn sprial_spawner(
 				spiral.spawn_count += 1;
 				let angle = spiral.angle * spiral.spawn_count as f32;
 				let dir = Vec2::from_angle(angle.to_radians());
-				commands.spawn((
-					get_projectile(
-						transform.translation().xy() + dir * 20.,
-						dir * 200.,
-						spiral.damage,
-						spiral.mesh.clone(),
-						spiral.material.clone(),
-					),
-					AudioPlayer::new(audio.spiral.clone()),
-					PlaybackSettings::ONCE.with_volume(Volume::Linear(0.5)),
-				));
+				let proj = commands
+					.spawn((
+						get_projectile(
+							transform.translation().xy() + dir * 20.,
+							dir * 200.,
+							spiral.damage,
+							spiral.knockback,
+							spiral.owner,
+							spiral.mesh.clone(),
+							spiral.material.clone(),
+						),
+						spiral.chain,
+						AudioPlayer::new(audio.spiral.clone()),
+						PlaybackSettings::ONCE.with_volume(Volume::Linear(0.5)),
+					))
+					.id();
+				if let Some(status) = spiral.status {
+					commands.entity(proj).insert(InflictStatus(status));
+				}
 			}
 		}
 	}
@@ -195,17 +523,20 @@ struct ProjBundle {
 	sensor: Sensor,
 	groups: CollisionGroups,
 	active: ActiveEvents,
+	knockback: Knockback,
 }
 
 fn get_projectile(
 	origin: Vec2,
 	vel: Vec2,
 	damage: f32,
+	knockback: f32,
+	owner: Owner,
 	mesh: Handle<Mesh>,
 	material: Handle<ColorMaterial>,
 ) -> ProjBundle {
 	return ProjBundle {
-		proj: Projectile::enemy(),
+		proj: Projectile(owner),
 		active: ActiveEvents::COLLISION_EVENTS,
 		damage: Damage(damage),
 		transform: Transform::from_translation(origin.extend(0.)),
@@ -215,7 +546,23 @@ fn get_projectile(
 		mesh: Mesh2d(mesh.clone()),
 		material: MeshMaterial2d(material.clone()),
 		collider: Collider::ball(0.5),
-		groups: CollisionGroups::new(ENEMY_PROJECTILE_GROUP, Group::ALL ^ ENEMY_PROJECTILE_GROUP),
+		groups: scatter_groups(owner),
 		sensor: Sensor,
+		knockback: Knockback(knockback),
 	};
 }
+
+//Enemy scatters hit their own side too so deaths can set each other off, the player's never turn on the player
+fn scatter_groups(owner: Owner) -> CollisionGroups {
+	match owner {
+		Owner::Player => CollisionGroups::new(PLAYER_PROJECTILE_GROUP, Group::ALL ^ PLAYER_OWNED_GROUP),
+		Owner::Enemy => CollisionGroups::new(ENEMY_PROJECTILE_GROUP, Group::ALL ^ ENEMY_PROJECTILE_GROUP),
+	}
+}
+
+fn explosion_groups(owner: Owner) -> CollisionGroups {
+	match owner {
+		Owner::Player => CollisionGroups::new(PLAYER_PROJECTILE_GROUP, Group::ALL ^ PLAYER_OWNED_GROUP),
+		Owner::Enemy => CollisionGroups::new(ENEMY_PROJECTILE_GROUP, Group::ALL),
+	}
+}
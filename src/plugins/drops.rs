@@ -4,17 +4,23 @@ use bevy_rapier2d::prelude::{Collider, CollisionEvent, CollisionGroups, RigidBod
 use crate::{
 	PLAYER_GROUP,
 	components::{
-		stats::{Health, MaxHealth},
+		drops::DropTable,
+		stats::{BaseStats, Experience, Health, MaxHealth, ModifierOp, StatKind, StatModifier, StatModifiers, Stats},
 		tags::Pickup,
 		utils::Lifetime,
-		weapons::{ProjectileType, Weapon, WeaponAuto, WeaponBurst, WeaponSpread},
+		weapons::{ProjectileType, Weapon, WeaponAuto, WeaponBeam, WeaponBurst, WeaponInventory, WeaponSpread},
+	},
+	plugins::{
+		player::Player,
+		utils::{play_audio_onshot, started_collision},
 	},
-	plugins::{player::Player, utils::play_audio_onshot},
 	resources::{
 		audio::AudioClips,
-		utils::{DeathEvent, DefaultProjTypes, RandomGen},
+		enemies::EnemyPrefabs,
+		upgrades::LevelUpDraft,
+		utils::{DeathEvent, DefaultProjTypes, DropPity, RandomGen},
 	},
-	state_management::GameplaySystems,
+	state_management::{GameStartSystems, GameplaySystems, RngSystems},
 };
 
 pub struct DropsPlugin;
@@ -22,10 +28,17 @@ pub struct DropsPlugin;
 impl Plugin for DropsPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_event::<PickupEvent>();
+		app.init_resource::<DropPity>();
 		app.add_systems(Startup, prepare_prefabs);
+		app.add_systems(Update, reset_pity.in_set(GameStartSystems));
 		app.add_systems(
-			Update,
-			(process_deaths, update_pickups, (pickup, pickup_events).chain()).in_set(GameplaySystems),
+			FixedUpdate,
+			(
+				process_deaths.in_set(RngSystems::Drops),
+				update_pickups,
+				(pickup, pickup_events).chain(),
+			)
+				.in_set(GameplaySystems),
 		);
 	}
 }
@@ -36,18 +49,26 @@ struct Prefabs {
 	pub weapon_auto: Entity,
 	pub weapon_burst: Entity,
 	pub weapon_spread: Entity,
+	pub weapon_beam: Entity,
 	pub health: Entity,
 	pub stat: Entity,
+	pub xp: Entity,
 }
 
 #[derive(Event, Clone, Copy)]
 struct PickupEvent(pub Pickup);
 
-fn prepare_prefabs(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn prepare_prefabs(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut materials: ResMut<Assets<ColorMaterial>>,
+) {
 	let health_sprite = asset_server.load("sprites/health.png");
 	let weapon_auto_sprite = asset_server.load("sprites/auto.png");
 	let weapon_spread_sprite = asset_server.load("sprites/spread.png");
 	let weapon_burst_sprite = asset_server.load("sprites/burst.png");
+	let weapon_beam_sprite = asset_server.load("sprites/beam.png");
 	let stat_sprite = asset_server.load("sprites/upgrade.png");
 	let weapon_auto = commands
 		.spawn((
@@ -85,6 +106,18 @@ fn prepare_prefabs(mut commands: Commands, asset_server: Res<AssetServer>) {
 			CollisionGroups::new(PLAYER_GROUP, PLAYER_GROUP),
 		))
 		.id();
+	let weapon_beam = commands
+		.spawn((
+			Name::new("Pickup: Weapon-Beam"),
+			Lifetime::new(30.),
+			Disabled,
+			Pickup::Weapon(Weapon::Beam),
+			RigidBody::Dynamic,
+			Collider::ball(10.),
+			Sprite::from_image(weapon_beam_sprite),
+			CollisionGroups::new(PLAYER_GROUP, PLAYER_GROUP),
+		))
+		.id();
 	let health = commands
 		.spawn((
 			Name::new("Pickup: Weapon-Health"),
@@ -109,50 +142,122 @@ fn prepare_prefabs(mut commands: Commands, asset_server: Res<AssetServer>) {
 			CollisionGroups::new(PLAYER_GROUP, PLAYER_GROUP),
 		))
 		.id();
+	let xp = commands
+		.spawn((
+			Name::new("Pickup: Xp"),
+			Lifetime::new(30.),
+			Disabled,
+			Pickup::Xp(1),
+			RigidBody::Dynamic,
+			Collider::ball(4.),
+			Mesh2d(meshes.add(Circle::new(4.))),
+			MeshMaterial2d(materials.add(Color::linear_rgb(0.2, 2.0, 2.5))),
+			CollisionGroups::new(PLAYER_GROUP, PLAYER_GROUP),
+		))
+		.id();
 	commands.insert_resource(Prefabs {
 		weapon_auto,
 		weapon_burst,
 		weapon_spread,
+		weapon_beam,
 		health,
 		stat,
+		xp,
 	});
 }
-const PICKUP_CHANCE: u32 = 5;
+impl Prefabs {
+	fn get(&self, pickup: Pickup) -> Entity {
+		return match pickup {
+			Pickup::Health => self.health,
+			Pickup::Stats => self.stat,
+			Pickup::Xp(_) => self.xp,
+			Pickup::Weapon(Weapon::Auto) => self.weapon_auto,
+			Pickup::Weapon(Weapon::Burst) => self.weapon_burst,
+			Pickup::Weapon(Weapon::Spread) => self.weapon_spread,
+			Pickup::Weapon(Weapon::Beam) => self.weapon_beam,
+		};
+	}
+}
+
+const PITY_STEP: f32 = 0.01;
+const LOW_HEALTH_BOOST: f32 = 4.;
 fn process_deaths(
 	mut deaths: EventReader<DeathEvent>,
 	prefabs: Res<Prefabs>,
+	enemy_prefabs: Res<EnemyPrefabs>,
+	tables: Query<&DropTable, With<Disabled>>,
+	player: Single<(&Health, &MaxHealth), With<Player>>,
+	mut pity: ResMut<DropPity>,
 	mut rng: ResMut<RandomGen>,
 	mut commands: Commands,
 ) {
+	let (health, max_health) = player.into_inner();
+	let missing_health = 1. - (health.0 / max_health.0).clamp(0., 1.);
+	let default_table = DropTable::default();
 	for death in deaths.read() {
 		if death.is_player {
 			continue;
 		}
-		if rng.range(0..100) <= PICKUP_CHANCE {
-			let pickup = rng.range(0..5);
-			let mut entity_commands = match pickup {
-				1 => commands.entity(prefabs.stat),
-				2 => commands.entity(prefabs.weapon_auto),
-				3 => commands.entity(prefabs.weapon_burst),
-				4 => commands.entity(prefabs.weapon_spread),
-				_ => commands.entity(prefabs.health),
+		commands
+			.entity(prefabs.xp)
+			.clone_and_spawn_with(|b| {
+				b.deny::<Disabled>();
+			})
+			.insert(Transform::from_translation(death.pos.extend(0.0)));
+
+		//Tables live on the archetype's prefab, enemies spawned some other way fall back to the default
+		let table = death
+			.archetype
+			.as_ref()
+			.and_then(|archetype| enemy_prefabs.0.get(archetype))
+			.and_then(|prefab| tables.get(*prefab).ok())
+			.unwrap_or(&default_table);
+		//Elites always drop, once for every affix they had
+		let mut drops = table.guaranteed + death.affixes.len() as u32;
+		if drops == 0 {
+			if rng.chance(table.chance + pity.0 as f32 * PITY_STEP) {
+				drops = 1;
+				pity.0 = 0;
+			} else {
+				pity.0 += 1;
+			}
+		}
+		for _ in 0..drops {
+			let weights = table.entries.iter().map(|entry| match entry.pickup {
+				Pickup::Health => entry.rarity.weight() * (1. + missing_health * LOW_HEALTH_BOOST),
+				_ => entry.rarity.weight(),
+			});
+			let Some(idx) = rng.weighted_index(weights) else {
+				break;
 			};
-			entity_commands
+			let pickup = table.entries[idx].pickup;
+			commands
+				.entity(prefabs.get(pickup))
 				.clone_and_spawn_with(|b| {
 					b.deny::<Disabled>();
 				})
-				.insert(Transform::from_translation(death.pos.extend(0.0)).with_scale(Vec3::splat(0.08)));
+				.insert((
+					pickup,
+					Transform::from_translation((death.pos + rng.point_on_circle_vec2(8.)).extend(0.0))
+						.with_scale(Vec3::splat(0.08)),
+				));
 		}
 	}
 }
 
-const PICKUP_RANGE: f32 = 200.;
-const PICKUP_RANGE_SQ: f32 = PICKUP_RANGE * PICKUP_RANGE;
+fn reset_pity(mut pity: ResMut<DropPity>) {
+	pity.0 = 0;
+}
 
-fn update_pickups(pickups: Query<(&mut Velocity, &Transform), With<Pickup>>, player: Single<&Transform, With<Player>>) {
+fn update_pickups(
+	pickups: Query<(&mut Velocity, &Transform), With<Pickup>>,
+	player: Single<(&Transform, &Stats), With<Player>>,
+) {
+	let (player, stats) = player.into_inner();
+	let range_sq = stats.0.pickup_range * stats.0.pickup_range;
 	for (mut vel, transform) in pickups {
 		let dir = player.translation.xy() - transform.translation.xy();
-		if dir.length_squared() > PICKUP_RANGE_SQ {
+		if dir.length_squared() > range_sq {
 			vel.linvel = Vec2::ZERO;
 			continue;
 		}
@@ -162,25 +267,19 @@ fn update_pickups(pickups: Query<(&mut Velocity, &Transform), With<Pickup>>, pla
 
 fn pickup(
 	mut collision_events: EventReader<CollisionEvent>,
-	pickups: Query<(Entity, &Pickup)>,
+	pickups: Query<&Pickup>,
 	player: Single<Entity, With<Player>>,
 	mut pickup_events: EventWriter<PickupEvent>,
 	mut commands: Commands,
 ) {
 	let player_entity = player.into_inner();
 	for event in collision_events.read() {
-		if let CollisionEvent::Started(a, b, _) = event {
-			if let Ok((e, pickup)) = pickups.get(*a) {
-				if *b == player_entity {
-					commands.entity(e).despawn();
-					pickup_events.write(PickupEvent(*pickup));
-				}
-			} else if let Ok((e, pickup)) = pickups.get(*b) {
-				if *a == player_entity {
-					commands.entity(e).despawn();
-					pickup_events.write(PickupEvent(*pickup));
-				}
-			}
+		if let Some((e, other)) = started_collision(event, |e| pickups.contains(e))
+			&& other == player_entity
+			&& let Ok(pickup) = pickups.get(e)
+		{
+			commands.entity(e).despawn();
+			pickup_events.write(PickupEvent(*pickup));
 		}
 	}
 }
@@ -190,51 +289,101 @@ fn pickup_events(
 	player: Single<
 		(
 			&mut Health,
-			&mut MaxHealth,
+			&mut Experience,
+			&BaseStats,
+			&mut StatModifiers,
 			&mut WeaponAuto,
 			&mut WeaponBurst,
 			&mut WeaponSpread,
-			&mut Weapon,
+			&mut WeaponBeam,
+			&mut WeaponInventory,
 			&mut ProjectileType,
 		),
 		With<Player>,
 	>,
 	mut projectiles: ResMut<DefaultProjTypes>,
+	mut draft: ResMut<LevelUpDraft>,
 	mut commands: Commands,
 	audio: Res<AudioClips>,
 ) {
-	let (mut health, mut max_health, mut auto, mut burst, mut spread, mut player_weapon, mut proj_type) =
-		player.into_inner();
+	let (
+		mut health,
+		mut experience,
+		base,
+		mut modifiers,
+		mut auto,
+		mut burst,
+		mut spread,
+		mut beam,
+		mut inventory,
+		mut proj_type,
+	) = player.into_inner();
 	const UPGRADE_RATE: f32 = 0.05;
 	for event in events.read() {
 		match event.0 {
 			Pickup::Health => {
 				play_audio_onshot(&mut commands, audio.heal.clone());
-				max_health.0 += 10.0;
-				health.0 = max_health.0;
+				modifiers.add(StatModifier::permanent(
+					StatKind::MaxHealth,
+					ModifierOp::Flat(10.),
+					"health_pickup",
+				));
+				modifiers.add(StatModifier::permanent(
+					StatKind::Regen,
+					ModifierOp::Flat(0.1),
+					"health_pickup",
+				));
+				modifiers.refresh(StatModifier::timed(
+					StatKind::Regen,
+					ModifierOp::Multiply(3.),
+					"health_pickup_burst",
+					5.,
+				));
+				health.0 = modifiers.resolve(StatKind::MaxHealth, base.0.max_health);
 			}
 			Pickup::Weapon(weapon) => {
 				play_audio_onshot(&mut commands, audio.weapon_switch.clone());
-				auto.upgrade(UPGRADE_RATE);
-				spread.upgrade(UPGRADE_RATE);
-				burst.upgrade(UPGRADE_RATE);
-				match weapon {
-					Weapon::Auto => auto.upgrade(UPGRADE_RATE),
-					Weapon::Spread => spread.upgrade(UPGRADE_RATE),
-					Weapon::Burst => burst.upgrade(UPGRADE_RATE),
-					Weapon::Beam => todo!(),
+				modifiers.add(StatModifier::permanent(
+					StatKind::ProjectileSpeed,
+					ModifierOp::AddPercent(UPGRADE_RATE),
+					"weapon_pickup",
+				));
+				//A weapon already held levels up, a new one starts over from its base
+				let levelled = inventory.add(weapon) > 1;
+				match (weapon, levelled) {
+					(Weapon::Auto, true) => auto.upgrade(UPGRADE_RATE),
+					(Weapon::Spread, true) => spread.upgrade(),
+					(Weapon::Burst, true) => burst.upgrade(),
+					(Weapon::Beam, true) => beam.upgrade(UPGRADE_RATE),
+					(Weapon::Auto, false) => *auto = default(),
+					(Weapon::Spread, false) => *spread = default(),
+					(Weapon::Burst, false) => *burst = default(),
+					(Weapon::Beam, false) => *beam = default(),
 				}
-				*player_weapon = weapon;
 			}
 			Pickup::Stats => {
 				play_audio_onshot(&mut commands, audio.pickup.clone());
+				modifiers.add(StatModifier::permanent(
+					StatKind::ProjectileSpeed,
+					ModifierOp::AddPercent(UPGRADE_RATE),
+					"stat_pickup",
+				));
+				modifiers.add(StatModifier::permanent(
+					StatKind::DashDistance,
+					ModifierOp::AddPercent(UPGRADE_RATE),
+					"stat_pickup",
+				));
+				//Multiplied so stacking pickups never brings the cooldown down to nothing
+				modifiers.add(StatModifier::permanent(
+					StatKind::DashCooldown,
+					ModifierOp::Multiply(1. - UPGRADE_RATE),
+					"stat_pickup",
+				));
 				projectiles.upgrade(UPGRADE_RATE);
-				*proj_type = match *proj_type {
-					ProjectileType::Basic { .. } => projectiles.basic,
-					ProjectileType::Piercing { .. } => projectiles.piercing,
-					ProjectileType::Bouncing { .. } => projectiles.bouncing,
-					ProjectileType::Grenade { .. } => projectiles.grenade,
-				};
+				*proj_type = projectiles.matching(&proj_type);
+			}
+			Pickup::Xp(xp) => {
+				draft.pending += experience.add(xp);
 			}
 		};
 	}
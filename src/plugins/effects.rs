@@ -3,11 +3,15 @@ use bevy_rapier2d::prelude::CollisionEvent;
 
 use crate::{
 	components::{
-		effects::{Explosion, ExplosionProgress},
-		stats::{Damage, Health, Life},
+		effects::{BaseColor, Explosion, ExplosionProgress, HitFlash},
+		stats::{Critical, Damage, Knockback},
+		status::StatusEffects,
+	},
+	plugins::utils::started_collision,
+	resources::{
+		effects::{ExplosionMeshData, HitStop},
+		utils::{DamageEvent, DamageKind},
 	},
-	plugins::projectiles::apply_damage,
-	resources::effects::ExplosionMeshData,
 	state_management::{GameOverSystems, GameplaySystems},
 };
 
@@ -17,15 +21,18 @@ impl Plugin for EffectsPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_systems(Startup, create_meshes);
 		app.add_systems(
-			Update,
+			FixedUpdate,
 			(animate_explosions, handle_explosion_hits).in_set(GameplaySystems),
 		);
 		app.add_systems(
-			Update,
+			FixedUpdate,
 			(animate_explosions, handle_explosion_hits).in_set(GameOverSystems),
 		);
 		app.add_systems(PostUpdate, init_explosions.in_set(GameplaySystems));
 		app.add_systems(PostUpdate, init_explosions.in_set(GameOverSystems));
+		app.init_resource::<HitStop>();
+		//Runs outside the gameplay sets so a hit-stop always ends, whatever state the game moved on to
+		app.add_systems(Update, (hit_stop, (tick_hit_flashes, update_tints).chain()));
 	}
 }
 
@@ -68,22 +75,102 @@ fn animate_explosions(
 	}
 }
 
-fn handle_explosion_hits(
-	mut explosions: Query<&Damage, With<Explosion>>,
-	mut targets: Query<(&mut Health, &mut Life)>,
-	mut collision_events: EventReader<CollisionEvent>,
+fn hit_stop(mut hit_stop: ResMut<HitStop>, real: Res<Time<Real>>, mut time: ResMut<Time<Virtual>>) {
+	let Some(timer) = &mut hit_stop.timer else {
+		return;
+	};
+	timer.tick(real.delta());
+	if timer.finished() {
+		hit_stop.timer = None;
+		time.unpause();
+	} else if !time.is_paused() {
+		time.pause();
+	}
+}
+
+//Only the end of a flash is flagged as a change, the tint is set when it starts and restored when it ends
+fn tick_hit_flashes(query: Query<&mut HitFlash>, time: Res<Time<Real>>) {
+	for mut flash in query {
+		if flash.0.finished() {
+			continue;
+		}
+		flash.bypass_change_detection().0.tick(time.delta());
+		if flash.0.finished() {
+			flash.set_changed();
+		}
+	}
+}
+
+//Tinted entities get a material of their own so the rest of their archetype keeps its color
+fn update_tints(
+	query: Query<
+		(
+			Entity,
+			&MeshMaterial2d<ColorMaterial>,
+			Option<&StatusEffects>,
+			Option<&HitFlash>,
+			Option<&BaseColor>,
+		),
+		Or<(Changed<StatusEffects>, Changed<HitFlash>)>,
+	>,
+	mut materials: ResMut<Assets<ColorMaterial>>,
+	mut commands: Commands,
 ) {
-	for event in collision_events.read() {
-		if let CollisionEvent::Started(entity_a, entity_b, _) = event {
-			if let Ok(damage) = explosions.get_mut(*entity_a) {
-				if let Ok((mut health, mut life)) = targets.get_mut(*entity_b) {
-					apply_damage(&mut health, &mut life, damage);
+	const FLASH: LinearRgba = LinearRgba::rgb(4., 4., 4.);
+	for (entity, material, statuses, flash, base) in query {
+		let tint = match flash {
+			Some(flash) if !flash.0.finished() => Some(FLASH),
+			_ => statuses.and_then(StatusEffects::tint),
+		};
+		match base {
+			Some(base) => {
+				if let Some(material) = materials.get_mut(&material.0) {
+					material.color = tinted(base.0, tint);
 				}
-			} else if let Ok(damage) = explosions.get_mut(*entity_b) {
-				if let Ok((mut health, mut life)) = targets.get_mut(*entity_a) {
-					apply_damage(&mut health, &mut life, damage);
+			}
+			None => {
+				if tint.is_none() {
+					continue;
 				}
+				let Some(mut own) = materials.get(&material.0).cloned() else {
+					continue;
+				};
+				let base = own.color;
+				own.color = tinted(base, tint);
+				commands
+					.entity(entity)
+					.insert((MeshMaterial2d(materials.add(own)), BaseColor(base)));
 			}
 		}
 	}
 }
+
+fn tinted(base: Color, tint: Option<LinearRgba>) -> Color {
+	return match tint {
+		Some(tint) => LinearRgba::from(base).mix(&tint, 0.6).into(),
+		None => base,
+	};
+}
+
+fn handle_explosion_hits(
+	explosions: Query<(&Explosion, &Damage, &Transform, Option<&Critical>, Option<&Knockback>)>,
+	mut collision_events: EventReader<CollisionEvent>,
+	mut damage_events: EventWriter<DamageEvent>,
+) {
+	for event in collision_events.read() {
+		if let Some((e, other)) = started_collision(event, |e| explosions.contains(e))
+			&& let Ok((explosion, damage, transform, crit, knockback)) = explosions.get(e)
+		{
+			damage_events.write(DamageEvent {
+				source: e,
+				target: other,
+				amount: damage.0,
+				kind: DamageKind::Explosive,
+				crit: crit.copied().unwrap_or_default(),
+				origin: transform.translation.xy(),
+				knockback: knockback.map_or(0., |knockback| knockback.0),
+				instigator: explosion.owner,
+			});
+		}
+	}
+}
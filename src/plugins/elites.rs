@@ -0,0 +1,249 @@
+use bevy::{ecs::entity_disabling::Disabled, platform::collections::HashMap, prelude::*};
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::{
+	components::{
+		archetype::EnemyArchetype,
+		death::{DeathScatter, ScatterPattern},
+		elite::{Affix, Elite, EliteAura, Shield, Teleporter},
+		stats::{
+			BaseStats, Health, Life, MaxHealth, ModifierOp, MoveSpeedStat, StatBlock, StatKind, StatModifier,
+			StatModifiers,
+		},
+	},
+	plugins::{death::death_events, player::Player},
+	resources::{
+		enemies::EnemyPrefabs,
+		utils::{DeathEvent, RandomGen},
+		waves::WaveDirector,
+	},
+	state_management::{GameplaySystems, RngSystems},
+};
+
+pub struct ElitesPlugin;
+
+impl Plugin for ElitesPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Startup, init_auras);
+		app.add_systems(
+			FixedUpdate,
+			(
+				promote_elites,
+				teleport_elites.in_set(RngSystems::Elites),
+				vampiric_drain,
+				split_on_death.before(death_events).in_set(RngSystems::Deaths),
+			)
+				.in_set(GameplaySystems),
+		);
+		app.add_systems(Update, pulse_auras.in_set(GameplaySystems));
+	}
+}
+
+const ELITE_BASE_CHANCE: f32 = 0.02;
+const ELITE_CHANCE_PER_WAVE: f32 = 0.01;
+const ELITE_MAX_CHANCE: f32 = 0.25;
+//Waves between each extra affix an elite can roll
+const ELITE_AFFIX_WAVES: u32 = 5;
+const ELITE_MAX_AFFIXES: usize = 3;
+const ELITE_HEALTH: f32 = 2.;
+const ELITE_SCALE: f32 = 1.3;
+const SHIELD_FRACTION: f32 = 0.5;
+const HASTE_MULTIPLIER: f32 = 1.6;
+const VOLATILE_COUNT: u32 = 2;
+const VOLATILE_DAMAGE: f32 = 2.;
+const VOLATILE_RANGE: f32 = 1.5;
+const SPLIT_COUNT: u32 = 2;
+const SPLIT_HEALTH: f32 = 0.5;
+const SPLIT_SCALE: f32 = 0.7;
+const SPLIT_SPREAD: f32 = 20.;
+const VAMPIRIC_RANGE: f32 = 150.;
+const VAMPIRIC_HEAL: f32 = 0.1;
+const TELEPORT_INTERVAL: f32 = 3.;
+const TELEPORT_DISTANCE: f32 = 120.;
+const AURA_RADIUS: f32 = 14.;
+
+#[derive(Resource)]
+struct AuraMeshes {
+	mesh: Handle<Mesh>,
+	materials: HashMap<Affix, Handle<ColorMaterial>>,
+}
+
+fn init_auras(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>) {
+	let materials = Affix::ALL
+		.into_iter()
+		.map(|affix| (affix, materials.add(Color::from(affix.color().with_alpha(0.5)))))
+		.collect();
+	commands.insert_resource(AuraMeshes {
+		mesh: meshes.add(Annulus::new(AURA_RADIUS - 2., AURA_RADIUS)),
+		materials,
+	});
+}
+
+//Elites get likelier and pick up more affixes as the waves go on and the director ramps up
+pub fn roll_elite(rng: &mut RandomGen, director: &WaveDirector) -> Option<Elite> {
+	let chance =
+		((ELITE_BASE_CHANCE + director.wave as f32 * ELITE_CHANCE_PER_WAVE) * director.intensity).min(ELITE_MAX_CHANCE);
+	if !rng.chance(chance) {
+		return None;
+	}
+	let count = (1 + (director.wave / ELITE_AFFIX_WAVES) as usize).min(ELITE_MAX_AFFIXES);
+	let mut pool = Affix::ALL.to_vec();
+	let mut affixes = Vec::with_capacity(count);
+	for _ in 0..count {
+		affixes.push(pool.remove(rng.range(0..pool.len())));
+	}
+	return Some(Elite(affixes));
+}
+
+fn promote_elites(
+	query: Query<
+		(
+			Entity,
+			&Elite,
+			&mut MaxHealth,
+			&mut Health,
+			&MoveSpeedStat,
+			&mut Transform,
+			Option<&mut DeathScatter>,
+		),
+		Added<Elite>,
+	>,
+	auras: Res<AuraMeshes>,
+	mut commands: Commands,
+) {
+	for (entity, elite, mut max_health, mut health, speed, mut transform, scatter) in query {
+		max_health.0 *= ELITE_HEALTH;
+		health.0 = max_health.0;
+		transform.scale *= ELITE_SCALE;
+		if elite.has(Affix::Shielded) {
+			commands.entity(entity).insert(Shield(max_health.0 * SHIELD_FRACTION));
+		}
+		if elite.has(Affix::Hasted) {
+			//Enemies don't carry a stat stack of their own until something needs to modify them
+			commands.entity(entity).insert((
+				BaseStats(StatBlock {
+					max_health: max_health.0,
+					move_speed: speed.0,
+					..default()
+				}),
+				StatModifiers(vec![StatModifier::permanent(
+					StatKind::MoveSpeed,
+					ModifierOp::Multiply(HASTE_MULTIPLIER),
+					"Hasted",
+				)]),
+			));
+		}
+		if elite.has(Affix::Volatile)
+			&& let Some(mut scatter) = scatter
+		{
+			for stage in &mut scatter.stages {
+				//Explosions go off once whatever the count, so they hit harder and further instead
+				if let ScatterPattern::Explosion { range, .. } = &mut stage.pattern {
+					*range *= VOLATILE_RANGE;
+					stage.damage *= VOLATILE_DAMAGE;
+				} else {
+					stage.count *= VOLATILE_COUNT;
+				}
+			}
+		}
+		if elite.has(Affix::Teleporting) {
+			commands
+				.entity(entity)
+				.insert(Teleporter(Timer::from_seconds(TELEPORT_INTERVAL, TimerMode::Repeating)));
+		}
+		//One ring per affix, stacked outwards
+		for (i, affix) in elite.0.iter().enumerate() {
+			commands.entity(entity).with_child((
+				Name::new("Elite Aura"),
+				EliteAura(1. + i as f32 * 0.25),
+				Mesh2d(auras.mesh.clone()),
+				MeshMaterial2d(auras.materials[affix].clone()),
+				Transform::from_translation(Vec3::NEG_Z),
+			));
+		}
+	}
+}
+
+fn teleport_elites(
+	query: Query<(&mut Teleporter, &mut Transform, &mut Velocity, &Life)>,
+	player: Single<&Transform, (With<Player>, Without<Teleporter>)>,
+	mut rng: ResMut<RandomGen>,
+	time: Res<Time>,
+) {
+	for (mut teleporter, mut transform, mut vel, life) in query {
+		if life.is_dead() {
+			continue;
+		}
+		teleporter.0.tick(time.delta());
+		if teleporter.0.just_finished() {
+			let offset = rng.point_on_unit_circle() * TELEPORT_DISTANCE;
+			transform.translation = player.translation + offset.extend(0.0);
+			vel.linvel = Vec2::ZERO;
+		}
+	}
+}
+
+fn vampiric_drain(
+	mut deaths: EventReader<DeathEvent>,
+	query: Query<(&Elite, &Transform, &MaxHealth, &mut Health, &Life)>,
+) {
+	let deaths: Vec<_> = deaths
+		.read()
+		.filter(|death| !death.is_player)
+		.map(|death| death.pos)
+		.collect();
+	if deaths.is_empty() {
+		return;
+	}
+	let range_sq = VAMPIRIC_RANGE * VAMPIRIC_RANGE;
+	for (elite, transform, max_health, mut health, life) in query {
+		if life.is_dead() || !elite.has(Affix::Vampiric) {
+			continue;
+		}
+		let pos = transform.translation.xy();
+		let drained = deaths
+			.iter()
+			.filter(|death| death.distance_squared(pos) <= range_sq)
+			.count();
+		health.0 = (health.0 + drained as f32 * max_health.0 * VAMPIRIC_HEAL).min(max_health.0);
+	}
+}
+
+//Splits come from the plain prefab so they can't be elites themselves
+fn split_on_death(
+	query: Query<(&Elite, &Life, &Transform, &EnemyArchetype)>,
+	prefabs: Res<EnemyPrefabs>,
+	health: Query<&MaxHealth, With<Disabled>>,
+	mut rng: ResMut<RandomGen>,
+	mut commands: Commands,
+) {
+	for (elite, life, transform, archetype) in query {
+		if life.is_alive() || life.1 || !elite.has(Affix::Splitting) {
+			continue;
+		}
+		let Some(prefab) = prefabs.0.get(&archetype.0) else {
+			continue;
+		};
+		let max_health = health.get(*prefab).map_or(100., |health| health.0) * SPLIT_HEALTH;
+		for _ in 0..SPLIT_COUNT {
+			let pos = transform.translation + rng.point_on_unit_circle().extend(0.0) * SPLIT_SPREAD;
+			commands
+				.entity(*prefab)
+				.clone_and_spawn_with(|builder| {
+					builder.deny::<Disabled>();
+				})
+				.insert((
+					Transform::from_translation(pos).with_scale(Vec3::splat(SPLIT_SCALE)),
+					Health(max_health),
+					MaxHealth(max_health),
+				));
+		}
+	}
+}
+
+fn pulse_auras(query: Query<(&mut Transform, &EliteAura)>, time: Res<Time>) {
+	let pulse = 1. + (time.elapsed_secs() * 4.).sin() * 0.1;
+	for (mut transform, aura) in query {
+		transform.scale = Vec3::splat(aura.0 * pulse);
+	}
+}
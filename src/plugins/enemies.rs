@@ -5,11 +5,16 @@ use bevy_rapier2d::prelude::*;
 
 use crate::{
 	components::{
-		ai::{AI, AITarget, ChargeAI, ChargeInfo, ChargeState, ChaseAI, HoverAI},
-		stats::{Health, Life, MoveSpeed, MoveSpeedMultiplier},
+		ai::{AI, AITarget, ChargeAI, ChargeInfo, ChargeState, ChaseAI, HoverAI, ShooterAI},
+		stats::{Knockback, Life, MoveSpeed, MoveSpeedMultiplier},
+		tags::Owner,
+		weapons::WeaponFiring,
+	},
+	plugins::utils::{play_audio_onshot, started_collision},
+	resources::{
+		audio::AudioClips,
+		utils::{DamageEvent, DamageKind},
 	},
-	plugins::utils::play_audio_onshot,
-	resources::audio::AudioClips,
 	state_management::{GameOverSystems, GameplaySystems},
 };
 
@@ -19,30 +24,45 @@ pub struct EnemiesPlugin;
 
 impl Plugin for EnemiesPlugin {
 	fn build(&self, app: &mut App) {
+		//Facing is settled before FixedUpdate so weapons fire along AITarget.look_at
 		app.add_systems(
-			PreUpdate,
-			(set_ai_chase_target, set_ai_hover_target, set_ai_charge_target).in_set(GameplaySystems),
+			FixedPreUpdate,
+			(
+				(set_ai_chase_target, set_ai_hover_target, set_ai_charge_target),
+				face_ai_target,
+				set_ai_firing,
+			)
+				.chain()
+				.in_set(GameplaySystems),
 		);
-		app.add_systems(Update, move_ai.in_set(GameplaySystems));
+		app.add_systems(FixedPreUpdate, stop_ai_firing.in_set(GameOverSystems));
+		app.add_systems(FixedUpdate, move_ai.in_set(GameplaySystems));
 		app.add_systems(
-			PostUpdate,
+			FixedPostUpdate,
 			(process_life, ai_charge_collision, ai_chase_collision).in_set(GameplaySystems),
 		);
 
 		app.add_systems(
-			PostUpdate,
+			FixedPostUpdate,
 			(process_life, ai_charge_collision, ai_chase_collision).in_set(GameOverSystems),
 		);
 
 		//Debugging
 		#[cfg(feature = "ai")]
-		app.add_systems(Update, (debug_ai, debug_hover_ai, debug_charge_ai));
+		if app.is_plugin_added::<bevy::gizmos::GizmoPlugin>() {
+			app.add_systems(Update, (debug_ai, debug_hover_ai, debug_charge_ai));
+		}
 	}
 }
 
-fn move_ai(query: Query<(&mut ExternalForce, &mut Transform, &MoveSpeed, &AI, &AITarget, &Life)>) {
-	for (mut force, mut transform, speed, ai, tgt, life) in query {
-		if ai.is_disabled() || life.is_dead() {
+fn move_ai(query: Query<(&mut ExternalForce, &Transform, &MoveSpeed, &AI, &AITarget, &Life)>) {
+	for (mut force, transform, speed, ai, tgt, life) in query {
+		if life.is_dead() {
+			continue;
+		}
+		//Stunned enemies stop pushing instead of carrying on in their last direction
+		if ai.is_disabled() {
+			force.force = Vec2::ZERO;
 			continue;
 		}
 		let move_dir = (tgt.move_to - transform.translation.xy()).normalize_or_zero();
@@ -51,7 +71,14 @@ fn move_ai(query: Query<(&mut ExternalForce, &mut Transform, &MoveSpeed, &AI, &A
 		} else {
 			force.force = Vec2::ZERO;
 		}
+	}
+}
 
+fn face_ai_target(query: Query<(&mut Transform, &AI, &AITarget, &Life)>) {
+	for (mut transform, ai, tgt, life) in query {
+		if ai.is_disabled() || life.is_dead() {
+			continue;
+		}
 		let look_dir = (tgt.look_at - transform.translation.xy()).normalize_or_zero();
 		if look_dir.length_squared() > f32::EPSILON {
 			transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, look_dir);
@@ -59,6 +86,27 @@ fn move_ai(query: Query<(&mut ExternalForce, &mut Transform, &MoveSpeed, &AI, &A
 	}
 }
 
+fn set_ai_firing(
+	query: Query<(&mut WeaponFiring, &Transform, &ShooterAI, &AI, &Life)>,
+	player: Single<(&Transform, &Life), With<Player>>,
+) {
+	let (player_transform, player_life) = player.into_inner();
+	for (mut firing, transform, shooter, ai, life) in query {
+		if ai.is_disabled() || life.is_dead() || player_life.is_dead() {
+			firing.0 = false;
+			continue;
+		}
+		let to_player = player_transform.translation.xy() - transform.translation.xy();
+		firing.0 = shooter.can_hit(transform.up().xy(), to_player);
+	}
+}
+
+fn stop_ai_firing(query: Query<&mut WeaponFiring, With<ShooterAI>>) {
+	for mut firing in query {
+		firing.0 = false;
+	}
+}
+
 #[cfg(feature = "ai")]
 fn debug_ai(query: Query<(&Transform, &AITarget)>, mut gizmos: Gizmos) {
 	for (transform, tgt) in query {
@@ -203,8 +251,13 @@ fn set_ai_charge_target(
 				info.cooldown.tick(time.delta());
 				tgt.look_and_move(transform.translation.xy() + info.charge_dir * 100.);
 				if info.cooldown.finished() {
-					life.0 = false;
-					life.1 = true;
+					if charge.repeat {
+						info.state = ChargeState::Chase;
+						move_multi.0 = 1.;
+					} else {
+						life.0 = false;
+						life.1 = true;
+					}
 				}
 			}
 		}
@@ -212,28 +265,29 @@ fn set_ai_charge_target(
 }
 
 fn ai_charge_collision(
-	mut chargers: Query<(&ChargeInfo, &ChargeAI, &mut Life)>,
-	mut other_entity: Query<&mut Health>,
+	mut chargers: Query<(&ChargeInfo, &ChargeAI, &mut Life, &Transform, Option<&Knockback>)>,
 	mut collisiion_events: EventReader<CollisionEvent>,
+	mut damage_events: EventWriter<DamageEvent>,
 ) {
 	for event in collisiion_events.read() {
-		if let CollisionEvent::Started(a, b, _) = event {
-			if let Ok((info, charge, mut life)) = chargers.get_mut(*a) {
-				process_collision(info, &mut life);
-				if let Ok(mut health) = other_entity.get_mut(*b) {
-					health.0 -= charge.hit_damage;
-				}
-			} else if let Ok((info, charge, mut life)) = chargers.get_mut(*b) {
-				process_collision(info, &mut life);
-				if let Ok(mut health) = other_entity.get_mut(*a) {
-					health.0 -= charge.hit_damage;
-				}
+		if let Some((e, other)) = started_collision(event, |e| chargers.contains(e))
+			&& let Ok((info, charge, mut life, transform, knockback)) = chargers.get_mut(e)
+		{
+			if let ChargeState::Charge = info.state
+				&& !charge.repeat
+			{
+				life.0 = false;
 			}
-		}
-	}
-	fn process_collision(info: &ChargeInfo, life: &mut Life) {
-		if let ChargeState::Charge = info.state {
-			life.0 = false;
+			damage_events.write(DamageEvent {
+				source: e,
+				target: other,
+				amount: charge.hit_damage,
+				kind: DamageKind::Contact,
+				crit: default(),
+				origin: transform.translation.xy(),
+				knockback: knockback.map_or(0., |knockback| knockback.0),
+				instigator: Owner::Enemy,
+			});
 		}
 	}
 }
@@ -244,16 +298,11 @@ fn ai_chase_collision(
 	mut collisiion_events: EventReader<CollisionEvent>,
 ) {
 	for event in collisiion_events.read() {
-		if let CollisionEvent::Started(a, b, _) = event {
-			if let Ok(mut life) = chasers.get_mut(*a) {
-				if let Ok(()) = player.get(*b) {
-					life.0 = false;
-				}
-			} else if let Ok(mut life) = chasers.get_mut(*b) {
-				if let Ok(()) = player.get(*a) {
-					life.0 = false;
-				}
-			}
+		if let Some((e, other)) = started_collision(event, |e| chasers.contains(e))
+			&& player.contains(other)
+			&& let Ok(mut life) = chasers.get_mut(e)
+		{
+			life.0 = false;
 		}
 	}
 }
@@ -3,17 +3,13 @@ use bevy::{
 		bloom::Bloom,
 		tonemapping::{DebandDither, Tonemapping},
 	},
+	ecs::schedule::{ExecutorKind, ScheduleLabel},
 	prelude::*,
 	window::PrimaryWindow,
 };
 use bevy_rapier2d::{
-	plugin::RapierConfiguration,
-	prelude::{Collider, Restitution},
-};
-#[cfg(feature = "inspect")]
-use iyes_perf_ui::{
-	PerfUiPlugin,
-	prelude::{PerfUiEntryFPS, PerfUiEntryFrameTimeWorst, PerfUiEntryRenderGpuTime},
+	plugin::{PhysicsSet, RapierConfiguration, TimestepMode},
+	prelude::{Collider, NoUserData, RapierPhysicsPlugin, Restitution},
 };
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
@@ -21,23 +17,30 @@ use rand_chacha::ChaChaRng;
 use crate::{
 	components::{tags::MainCamera, utils::Cleanable},
 	plugins::{
-		drops::DropsPlugin, effects::EffectsPlugin, game_over::GameOverPlugin, health::HealthPlugin,
-		main_menu::MainMenuPlugin, spawner::EnemySpawnerPlugin, types::TypesPlugin, ui::UIPlugin,
+		bosses::BossesPlugin, damage::DamagePlugin, drops::DropsPlugin, effects::EffectsPlugin, elites::ElitesPlugin,
+		game_over::GameOverPlugin, health::HealthPlugin, input::PlayerInputPlugin, main_menu::MainMenuPlugin,
+		replay::ReplayPlugin, spawner::EnemySpawnerPlugin, types::TypesPlugin, ui::UIPlugin, waves::WavesPlugin,
 		weapons::WeaponsPlugin,
 	},
 	resources::{
 		audio::AudioClips,
-		utils::{Fonts, RandomGen},
+		utils::{ArenaSize, Fonts, RandomGen, RunSeed},
 	},
 	state_management::{
 		GameCleanupSystems, GameOverSystems, GameStartSystems, GameWaitingSystems, GameplayState, GameplaySystems,
-		ResetSystems,
+		PlayerInputSystems, ResetSystems, RngSystems,
 	},
 };
 
 use super::{
-	death::DeathPlugin, enemies::EnemiesPlugin, hooks::HooksPlugin, player::PlayerPlugin,
-	projectiles::ProjectilesPlugin, utils::UtilsPlugin,
+	death::DeathPlugin,
+	enemies::EnemiesPlugin,
+	hooks::HooksPlugin,
+	player::PlayerPlugin,
+	progression::{ProgressionPlugin, draft_closed},
+	projectiles::ProjectilesPlugin,
+	status::StatusPlugin,
+	utils::UtilsPlugin,
 };
 
 pub struct GamePlugin;
@@ -46,6 +49,28 @@ impl Plugin for GamePlugin {
 	fn build(&self, app: &mut App) {
 		app.insert_state(GameplayState::Reset);
 
+		//Physics steps in lockstep with the gameplay systems so a run only depends on its seed and inputs
+		let timestep = app
+			.world()
+			.get_resource::<Time<Fixed>>()
+			.cloned()
+			.unwrap_or_default()
+			.timestep();
+		app.insert_resource(TimestepMode::Fixed {
+			dt: timestep.as_secs_f32(),
+			substeps: 1,
+		});
+		app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule());
+		app.add_systems(FixedFirst, match_physics_timestep);
+
+		//Parallel systems race for entity ids when spawning and read each other's events in whatever order they finish,
+		//which is enough to make runs diverge
+		for schedule in [FixedPreUpdate.intern(), FixedUpdate.intern(), FixedPostUpdate.intern()] {
+			app.edit_schedule(schedule, |schedule| {
+				schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+			});
+		}
+
 		app.add_plugins((
 			PlayerPlugin,
 			EnemiesPlugin,
@@ -61,25 +86,38 @@ impl Plugin for GamePlugin {
 			MainMenuPlugin,
 			GameOverPlugin,
 			UIPlugin,
-			DropsPlugin,
+			(
+				DropsPlugin,
+				DamagePlugin,
+				WavesPlugin,
+				PlayerInputPlugin,
+				ReplayPlugin,
+				StatusPlugin,
+				ProgressionPlugin,
+				ElitesPlugin,
+				BossesPlugin,
+			),
 		));
-		app.add_systems(PreStartup, (setup, spwan_bounds, load_auido));
+		app.init_resource::<ArenaSize>();
+		app.add_systems(
+			PreStartup,
+			(
+				setup,
+				fit_arena_to_window.before(spwan_bounds),
+				spwan_bounds,
+				load_auido,
+			),
+		);
 		app.add_systems(PostStartup, disable_gravity);
 		app.add_systems(Last, cleanup.in_set(GameCleanupSystems));
 		app.add_systems(Last, reset_transition.in_set(ResetSystems));
 		app.add_systems(Last, start_transition.in_set(GameStartSystems));
+		app.add_systems(Update, roll_seed.in_set(ResetSystems));
+		app.add_systems(PreUpdate, seed_run.in_set(GameStartSystems));
 
+		app.init_resource::<RunSeed>();
 		app.insert_resource(RandomGen(ChaChaRng::seed_from_u64(0)));
 
-		#[cfg(feature = "inspect")]
-		{
-			app.add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin::default())
-				// .add_plugins(bevy::diagnostic::EntityCountDiagnosticsPlugin)
-				.add_plugins(bevy::diagnostic::SystemInformationDiagnosticsPlugin)
-				.add_plugins(bevy::render::diagnostic::RenderDiagnosticsPlugin)
-				.add_plugins(PerfUiPlugin);
-		}
-
 		setup_sets(app);
 	}
 }
@@ -102,10 +140,69 @@ fn setup_sets(app: &mut App) {
 	app.configure_sets(PreUpdate, GameplaySystems.run_if(in_state(GameplayState::Playing)));
 	app.configure_sets(Update, GameplaySystems.run_if(in_state(GameplayState::Playing)));
 	app.configure_sets(PostUpdate, GameplaySystems.run_if(in_state(GameplayState::Playing)));
+	//A level-up draft stops the fixed tick gameplay but not the input, so the pick lands on the same tick in a replay
+	app.configure_sets(
+		FixedPreUpdate,
+		PlayerInputSystems
+			.run_if(in_state(GameplayState::Playing))
+			.before(GameplaySystems),
+	);
+	app.configure_sets(
+		FixedPreUpdate,
+		GameplaySystems
+			.run_if(in_state(GameplayState::Playing))
+			.run_if(draft_closed),
+	);
+	app.configure_sets(
+		FixedUpdate,
+		GameplaySystems
+			.run_if(in_state(GameplayState::Playing))
+			.run_if(draft_closed)
+			.before(PhysicsSet::SyncBackend),
+	);
+	app.configure_sets(
+		FixedPostUpdate,
+		GameplaySystems
+			.run_if(in_state(GameplayState::Playing))
+			.run_if(draft_closed),
+	);
 
 	app.configure_sets(PreUpdate, GameOverSystems.run_if(in_state(GameplayState::GameOver)));
 	app.configure_sets(Update, GameOverSystems.run_if(in_state(GameplayState::GameOver)));
 	app.configure_sets(PostUpdate, GameOverSystems.run_if(in_state(GameplayState::GameOver)));
+	app.configure_sets(
+		FixedPreUpdate,
+		GameOverSystems.run_if(in_state(GameplayState::GameOver)),
+	);
+	app.configure_sets(
+		FixedUpdate,
+		GameOverSystems
+			.run_if(in_state(GameplayState::GameOver))
+			.before(PhysicsSet::SyncBackend),
+	);
+	app.configure_sets(
+		FixedPostUpdate,
+		GameOverSystems.run_if(in_state(GameplayState::GameOver)),
+	);
+
+	//Systems sharing the run's rng take their turns in a set order rather than the order they happened to be added in
+	app.configure_sets(
+		FixedUpdate,
+		(
+			RngSystems::Spawning,
+			RngSystems::Elites,
+			RngSystems::Bosses,
+			RngSystems::Weapons,
+			RngSystems::Hits,
+			RngSystems::Deaths,
+			RngSystems::Drops,
+		)
+			.chain(),
+	);
+	app.configure_sets(
+		FixedPostUpdate,
+		(RngSystems::Deaths, RngSystems::Damage, RngSystems::Drafts).chain(),
+	);
 
 	app.configure_sets(PreUpdate, GameCleanupSystems.run_if(in_state(GameplayState::Cleanup)));
 	app.configure_sets(Update, GameCleanupSystems.run_if(in_state(GameplayState::Cleanup)));
@@ -126,6 +223,28 @@ fn cleanup(query: Query<Entity, With<Cleanable>>, mut commands: Commands, mut ne
 	info!("Moving to Reset");
 }
 
+//Every run gets a fresh seed unless one was picked on the command line or in the menu
+fn roll_seed(mut seed: ResMut<RunSeed>) {
+	if !seed.locked {
+		*seed = RunSeed::random();
+	}
+}
+
+fn seed_run(seed: Res<RunSeed>, mut rng: ResMut<RandomGen>) {
+	rng.0 = ChaChaRng::seed_from_u64(seed.seed);
+	info!("Starting run with seed {}", seed.seed);
+}
+
+//Keeps physics in step when the fixed timestep is changed after the plugin is built
+fn match_physics_timestep(time: Res<Time<Fixed>>, mut mode: ResMut<TimestepMode>) {
+	if let TimestepMode::Fixed { substeps, .. } = *mode {
+		mode.set_if_neq(TimestepMode::Fixed {
+			dt: time.timestep().as_secs_f32(),
+			substeps,
+		});
+	}
+}
+
 fn start_transition(mut next: ResMut<NextState<GameplayState>>) {
 	next.set(GameplayState::Playing);
 	info!("Moving to Playing");
@@ -140,12 +259,6 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 		Bloom::default(),
 		DebandDither::Enabled,
 	));
-	#[cfg(feature = "inspect")]
-	commands.spawn((
-		PerfUiEntryFPS::default(),
-		PerfUiEntryRenderGpuTime::default(),
-		PerfUiEntryFrameTimeWorst::default(),
-	));
 
 	commands.insert_resource(Fonts {
 		noto: asset_server.load("fonts/NotoSans-VariableFont_wdth,wght.ttf"),
@@ -158,8 +271,15 @@ fn disable_gravity(mut cfg: Single<&mut RapierConfiguration>) {
 	cfg.gravity = Vec2::ZERO;
 }
 
-fn spwan_bounds(mut commands: Commands, window: Single<&Window, With<PrimaryWindow>>) {
-	let size = window.size();
+//Without a window the arena keeps its configured size
+fn fit_arena_to_window(window: Option<Single<&Window, With<PrimaryWindow>>>, mut arena: ResMut<ArenaSize>) {
+	if let Some(window) = window {
+		arena.0 = window.size();
+	}
+}
+
+fn spwan_bounds(mut commands: Commands, arena: Res<ArenaSize>) {
+	let size = arena.0;
 	//Left
 	commands.spawn((
 		Transform::from_xyz(-size.x / 2., 0.0, 0.0),
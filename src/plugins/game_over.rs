@@ -5,7 +5,8 @@ use crate::{
 	plugins::utils::play_audio_onshot,
 	resources::{
 		audio::AudioClips,
-		utils::{Fonts, KillCount},
+		input::{Action, ActionInput, ActionMap},
+		utils::{ChainStats, Fonts, KillCount},
 	},
 	state_management::{GameOverState, GameOverSystems, GameplayState},
 };
@@ -32,6 +33,8 @@ fn spawn_menu(
 	fonts: Res<Fonts>,
 	audio: Res<AudioClips>,
 	kill_count: Res<KillCount>,
+	chains: Res<ChainStats>,
+	actions: Res<ActionMap>,
 ) {
 	commands.spawn((
 		Cleanable,
@@ -55,10 +58,29 @@ fn spawn_menu(
 		},
 		TextLayout::new_with_justify(JustifyText::Center),
 	));
+	let stats = [
+		format!("Score: {}", chains.score.round()),
+		format!("Longest Chain: {} (Depth {})", chains.longest, chains.deepest),
+		format!("Chain Kills: {}", chains.chain_kills),
+		format!("Best Combo: x{:.1}", chains.best_multiplier),
+	];
+	for (i, stat) in stats.into_iter().enumerate() {
+		commands.spawn((
+			Cleanable,
+			Transform::from_xyz(0.0, -25. * (i + 1) as f32, 0.0),
+			Text2d::new(stat),
+			TextFont {
+				font: fonts.noto_thin.clone(),
+				font_size: 20.,
+				..default()
+			},
+			TextLayout::new_with_justify(JustifyText::Center),
+		));
+	}
 	commands.spawn((
 		Cleanable,
-		Transform::from_xyz(0.0, -30., 0.0),
-		Text2d::new("Press [R] to Restart"),
+		Transform::from_xyz(0.0, -160., 0.0),
+		Text2d::new(format!("Press [{}] to Restart", actions.label(Action::Restart))),
 		TextFont {
 			font: fonts.noto_thin.clone(),
 			font_size: 20.,
@@ -71,11 +93,11 @@ fn spawn_menu(
 }
 
 fn menu(
-	key: Res<ButtonInput<KeyCode>>,
+	actions: ActionInput,
 	mut next_gm: ResMut<NextState<GameOverState>>,
 	mut next_game: ResMut<NextState<GameplayState>>,
 ) {
-	if key.just_pressed(KeyCode::KeyR) {
+	if actions.just_pressed(Action::Restart) {
 		next_gm.set(GameOverState::Wait);
 		next_game.set(GameplayState::Cleanup);
 		info!("Moving to Cleanup");
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use bevy::{
+	asset::AssetMetaCheck, input::InputPlugin, prelude::*, scene::ScenePlugin, state::app::StatesPlugin,
+	time::TimeUpdateStrategy,
+};
+
+use crate::resources::{input::ActionMap, replay::ReplayRecorder, utils::ArenaSize};
+
+//Stand-in for DefaultPlugins that runs the game without a window, renderer or audio device.
+//Every update advances time by exactly one fixed timestep so simulations are repeatable.
+pub struct HeadlessPlugin {
+	pub arena_size: Vec2,
+	pub timestep: Duration,
+}
+
+impl Default for HeadlessPlugin {
+	fn default() -> Self {
+		Self {
+			arena_size: ArenaSize::default().0,
+			timestep: Time::<Fixed>::default().timestep(),
+		}
+	}
+}
+
+impl Plugin for HeadlessPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_plugins((
+			MinimalPlugins,
+			StatesPlugin,
+			TransformPlugin,
+			InputPlugin,
+			AssetPlugin {
+				meta_check: AssetMetaCheck::Never,
+				..default()
+			},
+			ScenePlugin,
+		));
+		//Normally registered by the render, text and audio plugins, the assets just never get drawn or played
+		app.init_asset::<Mesh>()
+			.init_asset::<ColorMaterial>()
+			.init_asset::<Image>()
+			.init_asset::<Font>()
+			.init_asset::<AudioSource>();
+		app.insert_resource(ArenaSize(self.arena_size));
+		app.insert_resource(TimeUpdateStrategy::ManualDuration(self.timestep));
+		//Simulated runs stay in memory and leave the player's replay and bindings alone
+		app.insert_resource(ActionMap::default());
+		app.insert_resource(ReplayRecorder {
+			path: None,
+			..default()
+		});
+	}
+}
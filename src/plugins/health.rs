@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
-	components::stats::{Health, HealthRegen, MaxHealth},
+	components::stats::{Health, HealthRegen, IFrames, MaxHealth},
 	state_management::{GameOverSystems, GameplaySystems},
 };
 
@@ -9,9 +9,9 @@ pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(PostUpdate, clamp_health.in_set(GameplaySystems));
-		app.add_systems(PostUpdate, clamp_health.in_set(GameOverSystems));
-		app.add_systems(Update, health_regen.in_set(GameplaySystems));
+		app.add_systems(FixedPostUpdate, clamp_health.in_set(GameplaySystems));
+		app.add_systems(FixedPostUpdate, clamp_health.in_set(GameOverSystems));
+		app.add_systems(FixedUpdate, (health_regen, tick_iframes).in_set(GameplaySystems));
 	}
 }
 
@@ -21,6 +21,14 @@ fn clamp_health(query: Query<(&mut Health, &MaxHealth)>) {
 	}
 }
 
+fn tick_iframes(query: Query<&mut IFrames>, time: Res<Time>) {
+	for mut iframes in query {
+		if iframes.is_active() {
+			iframes.timer.tick(time.delta());
+		}
+	}
+}
+
 fn health_regen(query: Query<(&mut Health, &HealthRegen, &MaxHealth)>, time: Res<Time>) {
 	for (mut health, regen, max) in query {
 		if health.0 < max.0 {
@@ -0,0 +1,212 @@
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*, window::PrimaryWindow};
+
+use crate::{
+	components::{input::PlayerIntent, tags::Enemy, tags::MainCamera},
+	plugins::player::Player,
+	resources::{
+		input::{Action, ActionInput, ActionMap, InputSource, LiveInput, StickSettings, TickInput},
+		replay::{InputFrame, ReplayPlayback},
+		upgrades::LevelUpDraft,
+	},
+	state_management::{GameStartSystems, GameplaySystems, InputSourceSystems, PlayerInputSystems},
+};
+
+//Projectile types the number keys and cycling go through
+pub const PROJECTILE_SLOTS: u8 = 5;
+
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+	fn build(&self, app: &mut App) {
+		if !app.world().contains_resource::<ActionMap>() {
+			app.insert_resource(ActionMap::load_or_create());
+		}
+		app.init_resource::<InputSource>();
+		app.init_resource::<LiveInput>();
+		app.init_resource::<StickSettings>();
+		app.init_resource::<TickInput>();
+		app.add_systems(PreUpdate, reset_live_input.in_set(GameStartSystems));
+		app.add_systems(
+			Update,
+			(keyboard_movement, mouse_aim, action_buttons, gamepad_sticks)
+				.chain()
+				.in_set(GameplaySystems)
+				.run_if(resource_equals(InputSource::Live)),
+		);
+		app.configure_sets(FixedPreUpdate, InputSourceSystems.in_set(PlayerInputSystems));
+		app.add_systems(
+			FixedPreUpdate,
+			(
+				live_source.run_if(resource_equals(InputSource::Live)),
+				replay_source.run_if(resource_equals(InputSource::Replay)),
+				bot_source.run_if(resource_equals(InputSource::Bot)),
+			)
+				.in_set(InputSourceSystems),
+		);
+		app.add_systems(
+			FixedPreUpdate,
+			update_intent.after(InputSourceSystems).in_set(PlayerInputSystems),
+		);
+	}
+}
+
+fn reset_live_input(mut live: ResMut<LiveInput>, playback: Option<ResMut<ReplayPlayback>>) {
+	*live = LiveInput::default();
+	if let Some(mut playback) = playback {
+		playback.tick = 0;
+	}
+}
+
+fn keyboard_movement(mut live: ResMut<LiveInput>, actions: ActionInput) {
+	let mut move_dir = Vec2::ZERO;
+
+	if actions.pressed(Action::MoveUp) {
+		move_dir.y = 1.0;
+	} else if actions.pressed(Action::MoveDown) {
+		move_dir.y = -1.0;
+	}
+
+	if actions.pressed(Action::MoveRight) {
+		move_dir.x = 1.0;
+	} else if actions.pressed(Action::MoveLeft) {
+		move_dir.x = -1.0;
+	}
+
+	live.move_dir = move_dir.normalize_or_zero();
+}
+
+fn mouse_aim(
+	mut live: ResMut<LiveInput>,
+	mouse_motion: Res<AccumulatedMouseMotion>,
+	player: Single<&Transform, With<Player>>,
+	cam: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+	window: Single<&Window, With<PrimaryWindow>>,
+) {
+	if mouse_motion.delta != Vec2::ZERO {
+		live.stick_aim = false;
+	}
+	if live.stick_aim {
+		return;
+	}
+	let (cam, cam_transform) = cam.into_inner();
+	if let Some(m_pos) = window.cursor_position()
+		&& let Ok(world_pos) = cam.viewport_to_world_2d(cam_transform, m_pos)
+		&& let Some(dir) = (world_pos - player.translation.xy()).try_normalize()
+	{
+		live.aim_dir = dir;
+	}
+}
+
+//Picks and play actions share keys, so only the ones for whether a draft is open are read
+fn action_buttons(mut live: ResMut<LiveInput>, actions: ActionInput, draft: Res<LevelUpDraft>) {
+	if draft.is_open() {
+		for choice in 1..=3 {
+			if actions.just_pressed(Action::PickUpgrade(choice)) {
+				live.pick = choice;
+			}
+		}
+		return;
+	}
+	live.fire = actions.pressed(Action::Fire);
+	live.dash |= actions.just_pressed(Action::Dash);
+	for slot in 1..=4 {
+		if actions.just_pressed(Action::SelectWeapon(slot)) {
+			live.weapon = slot;
+		}
+	}
+	live.next_weapon |= actions.just_pressed(Action::NextWeapon);
+	for slot in 1..=PROJECTILE_SLOTS {
+		if actions.just_pressed(Action::SelectProjectile(slot)) {
+			live.slot = slot;
+			live.select = slot;
+		}
+	}
+	let cycle = actions.just_pressed(Action::NextProjectile) as i8 - actions.just_pressed(Action::PrevProjectile) as i8;
+	if cycle != 0 {
+		live.slot = (live.slot as i8 - 1 + cycle).rem_euclid(PROJECTILE_SLOTS as i8) as u8 + 1;
+		live.select = live.slot;
+	}
+}
+
+//Sticks only take over while they are pushed past the deadzone, so keyboard and mouse keep working alongside
+fn gamepad_sticks(
+	mut live: ResMut<LiveInput>,
+	gamepads: Query<&Gamepad>,
+	settings: Res<StickSettings>,
+	player: Single<&Transform, With<Player>>,
+	enemies: Query<&Transform, With<Enemy>>,
+) {
+	let pos = player.translation.xy();
+	for gamepad in gamepads {
+		let move_dir = settings.apply_deadzone(gamepad.left_stick());
+		if move_dir != Vec2::ZERO {
+			live.move_dir = move_dir;
+		}
+		let aim = settings.apply_deadzone(gamepad.right_stick());
+		if aim != Vec2::ZERO {
+			let enemies = enemies.iter().map(|transform| transform.translation.xy());
+			live.aim_dir = settings.assist(aim.normalize(), pos, enemies);
+			live.stick_aim = true;
+		}
+	}
+}
+
+fn live_source(mut live: ResMut<LiveInput>, mut tick: ResMut<TickInput>) {
+	tick.0 = InputFrame {
+		select: live.select,
+		dash: live.dash,
+		pick: live.pick,
+		weapon: live.weapon,
+		next_weapon: live.next_weapon,
+		..InputFrame::new(live.move_dir, live.aim_dir, live.fire)
+	};
+	live.select = 0;
+	live.dash = false;
+	live.pick = 0;
+	live.weapon = 0;
+	live.next_weapon = false;
+}
+
+fn replay_source(playback: Option<ResMut<ReplayPlayback>>, mut tick: ResMut<TickInput>) {
+	tick.0 = match playback {
+		Some(mut playback) => playback.next_frame(),
+		None => InputFrame::default(),
+	};
+}
+
+//Keeps its distance from the closest enemy while shooting at it, always takes the first upgrade on offer
+fn bot_source(
+	player: Single<&Transform, With<Player>>,
+	enemies: Query<&Transform, With<Enemy>>,
+	mut tick: ResMut<TickInput>,
+) {
+	const SAFE_DISTANCE: f32 = 250.;
+	let pos = player.translation.xy();
+	let closest = enemies
+		.iter()
+		.map(|transform| transform.translation.xy())
+		.min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+
+	let Some(target) = closest else {
+		//Drift back to the middle of the arena while waiting for the next wave
+		tick.0 = InputFrame {
+			pick: 1,
+			..InputFrame::new(-pos / SAFE_DISTANCE, Vec2::Y, false)
+		};
+		return;
+	};
+	let to_target = target - pos;
+	let move_dir = if to_target.length() < SAFE_DISTANCE {
+		-to_target.normalize_or_zero()
+	} else {
+		to_target.perp().normalize_or_zero()
+	};
+	tick.0 = InputFrame {
+		pick: 1,
+		..InputFrame::new(move_dir, to_target.normalize_or(Vec2::Y), true)
+	};
+}
+
+pub fn update_intent(mut intent: Single<&mut PlayerIntent, With<Player>>, tick: Res<TickInput>) {
+	**intent = tick.0.into();
+}
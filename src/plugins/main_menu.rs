@@ -4,7 +4,12 @@ use crate::{
 	NAME,
 	components::tags::MainMenu,
 	plugins::utils::play_audio_onshot,
-	resources::{audio::AudioClips, utils::Fonts},
+	resources::{
+		audio::AudioClips,
+		input::{Action, ActionInput, ActionMap},
+		replay::ReplayPlayback,
+		utils::{Fonts, RunSeed},
+	},
 	state_management::{GameWaitingSystems, GameplayState, GameplaySystems, ResetSystems},
 };
 
@@ -13,12 +18,28 @@ pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_systems(Update, spawn_menu.in_set(ResetSystems));
-		app.add_systems(Update, menu.in_set(GameWaitingSystems));
+		app.add_systems(Update, (menu, update_seed_text).in_set(GameWaitingSystems));
+		//A replay only makes sense with the seed it was recorded with
+		app.add_systems(
+			Update,
+			edit_seed
+				.in_set(GameWaitingSystems)
+				.run_if(not(resource_exists::<ReplayPlayback>)),
+		);
 		app.add_systems(PreUpdate, clean_menu.in_set(GameplaySystems));
 	}
 }
 
-fn spawn_menu(mut commands: Commands, fonts: Res<Fonts>) {
+#[derive(Component)]
+struct SeedText;
+
+fn spawn_menu(
+	mut commands: Commands,
+	fonts: Res<Fonts>,
+	playback: Option<Res<ReplayPlayback>>,
+	actions: Res<ActionMap>,
+) {
+	let start = actions.label(Action::Start);
 	commands.spawn((
 		MainMenu,
 		Transform::from_xyz(0.0, 50., 0.0),
@@ -34,7 +55,11 @@ fn spawn_menu(mut commands: Commands, fonts: Res<Fonts>) {
 	commands.spawn((
 		MainMenu,
 		Transform::from_xyz(0.0, -30., 0.0),
-		Text2d::new("Press [SPACE] to Start"),
+		Text2d::new(if playback.is_some() {
+			format!("Press [{start}] to Watch Replay")
+		} else {
+			format!("Press [{start}] to Start")
+		}),
 		TextFont {
 			font: fonts.noto_thin.clone(),
 			font_size: 20.,
@@ -42,6 +67,19 @@ fn spawn_menu(mut commands: Commands, fonts: Res<Fonts>) {
 		},
 		TextLayout::new_with_justify(JustifyText::Center),
 	));
+
+	commands.spawn((
+		MainMenu,
+		SeedText,
+		Transform::from_xyz(0.0, -70., 0.0),
+		Text2d::default(),
+		TextFont {
+			font: fonts.noto_thin.clone(),
+			font_size: 15.,
+			..default()
+		},
+		TextLayout::new_with_justify(JustifyText::Center),
+	));
 }
 
 fn clean_menu(query: Query<Entity, With<MainMenu>>, mut commands: Commands) {
@@ -51,14 +89,79 @@ fn clean_menu(query: Query<Entity, With<MainMenu>>, mut commands: Commands) {
 }
 
 fn menu(
-	key: Res<ButtonInput<KeyCode>>,
+	actions: ActionInput,
 	mut next: ResMut<NextState<GameplayState>>,
 	mut commands: Commands,
 	audio: Res<AudioClips>,
 ) {
-	if key.just_pressed(KeyCode::Space) {
+	if actions.just_pressed(Action::Start) {
 		info!("Moving to Waiting");
 		next.set(GameplayState::Startup);
 		play_audio_onshot(&mut commands, audio.start.clone());
 	}
 }
+
+//Typing digits or stepping up and down sets a specific seed, [N] goes back to a random one
+fn edit_seed(key: Res<ButtonInput<KeyCode>>, actions: ActionInput, mut seed: ResMut<RunSeed>) {
+	if actions.just_pressed(Action::RandomSeed) {
+		*seed = RunSeed::random();
+		return;
+	}
+	if key.just_pressed(KeyCode::Backspace) {
+		*seed = RunSeed::fixed(seed.seed / 10);
+		return;
+	}
+	if actions.just_pressed(Action::SeedUp) {
+		*seed = RunSeed::fixed(seed.seed.wrapping_add(1));
+		return;
+	}
+	if actions.just_pressed(Action::SeedDown) {
+		*seed = RunSeed::fixed(seed.seed.wrapping_sub(1));
+		return;
+	}
+	for digit in key.get_just_pressed().filter_map(digit_value) {
+		let current = if seed.locked { seed.seed } else { 0 };
+		if let Some(value) = current.checked_mul(10).and_then(|v| v.checked_add(digit)) {
+			*seed = RunSeed::fixed(value);
+		}
+	}
+}
+
+fn digit_value(key: &KeyCode) -> Option<u64> {
+	let digit = match key {
+		KeyCode::Digit0 | KeyCode::Numpad0 => 0,
+		KeyCode::Digit1 | KeyCode::Numpad1 => 1,
+		KeyCode::Digit2 | KeyCode::Numpad2 => 2,
+		KeyCode::Digit3 | KeyCode::Numpad3 => 3,
+		KeyCode::Digit4 | KeyCode::Numpad4 => 4,
+		KeyCode::Digit5 | KeyCode::Numpad5 => 5,
+		KeyCode::Digit6 | KeyCode::Numpad6 => 6,
+		KeyCode::Digit7 | KeyCode::Numpad7 => 7,
+		KeyCode::Digit8 | KeyCode::Numpad8 => 8,
+		KeyCode::Digit9 | KeyCode::Numpad9 => 9,
+		_ => return None,
+	};
+	return Some(digit);
+}
+
+fn update_seed_text(
+	mut text: Single<&mut Text2d, With<SeedText>>,
+	seed: Res<RunSeed>,
+	playback: Option<Res<ReplayPlayback>>,
+	actions: Res<ActionMap>,
+) {
+	if !seed.is_changed() && !text.0.is_empty() {
+		return;
+	}
+	text.0 = if playback.is_some() {
+		format!("Seed: {} (from replay)", seed.seed)
+	} else if seed.locked {
+		format!(
+			"Seed: {} (type to change, [{}] for random)",
+			seed.seed,
+			actions.label(Action::RandomSeed)
+		)
+	} else {
+		format!("Seed: {} (type a seed to pick one)", seed.seed)
+	};
+}
@@ -1,14 +1,24 @@
+mod bosses;
+mod damage;
 mod death;
 mod effects;
+mod elites;
 mod enemies;
 mod game;
+mod headless;
 mod hooks;
-mod player;
+mod input;
+pub mod player;
+mod progression;
 mod projectiles;
+mod replay;
 mod spawner;
+mod status;
 mod types;
 mod utils;
+mod waves;
 pub use game::*;
+pub use headless::*;
 mod drops;
 mod game_over;
 mod health;
@@ -1,24 +1,87 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
 	PLAYER_GROUP,
 	components::{
-		stats::{Health, Life, MaxHealth, MoveSpeed, MoveSpeedStat},
-		tags::MainCamera,
-		ui::{HealthBar, HealthBarText, HealthTextDisplayMode},
-		utils::Cleanable,
-		weapons::{ProjectileType, Weapon, WeaponFiring},
+		input::PlayerIntent,
+		stats::{
+			BaseStats, Experience, HealthRegen, IFrames, Life, MaxHealth, MoveSpeed, MoveSpeedMultiplier,
+			MoveSpeedStat, StatBlock, Stats,
+		},
+		status::{StatusApplication, StatusEffects, StatusKind},
+		ui::{DashBar, HealthBar, HealthBarText, HealthTextDisplayMode},
+		utils::{Cleanable, Lifetime},
+		weapons::{Fragmentation, ProjectileType, ScatterOnHit, Weapon, WeaponFiring, WeaponInventory},
+	},
+	plugins::{input::update_intent, utils::play_audio_onshot},
+	resources::{
+		audio::AudioClips,
+		effects::AfterimageMaterial,
+		utils::{ArenaSize, DefaultProjTypes, Fonts},
 	},
-	resources::utils::{DefaultProjTypes, Fonts},
 	state_management::{GameStartSystems, GameplayState, GameplaySystems},
 };
 
+#[cfg(debug_assertions)]
+use crate::components::stats::Health;
+
+//The dash covers its distance over this long
+const DASH_TIME: f32 = 0.15;
+const AFTERIMAGE_TIME: f32 = 0.2;
+const WEAPON_SLOTS: usize = 3;
+
 pub struct PlayerPlugin;
 #[derive(Component, Default, Reflect)]
-#[require(MaxHealth(200.), MoveSpeedStat(100.), Transform, Visibility, Weapon, Cleanable)]
+#[require(
+	MaxHealth(200.),
+	MoveSpeedStat(100.),
+	MoveSpeedMultiplier,
+	StatusEffects,
+	Dash,
+	Experience,
+	ScatterOnHit,
+	Transform,
+	Visibility,
+	Weapon,
+	WeaponInventory,
+	PlayerIntent,
+	Cleanable
+)]
 pub struct Player;
 
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Dash {
+	pub dir: Vec2,
+	pub active: Timer,
+	pub cooldown: Timer,
+}
+
+impl Default for Dash {
+	//Ready to go from the start
+	fn default() -> Self {
+		let mut active = Timer::from_seconds(DASH_TIME, TimerMode::Once);
+		active.tick(active.duration());
+		let mut cooldown = Timer::from_seconds(1., TimerMode::Once);
+		cooldown.tick(cooldown.duration());
+		Self {
+			dir: Vec2::Y,
+			active,
+			cooldown,
+		}
+	}
+}
+
+impl Dash {
+	pub fn is_dashing(&self) -> bool {
+		!self.active.finished()
+	}
+}
+
+#[derive(Component)]
+struct Afterimage;
+
 impl Plugin for PlayerPlugin {
 	fn build(&self, app: &mut App) {
 		app.insert_resource(DefaultProjTypes {
@@ -26,12 +89,20 @@ impl Plugin for PlayerPlugin {
 				damage: 50.,
 				speed: 500.,
 				multishot: 1,
+				status: None,
+				fragmentation: Fragmentation::default(),
 			},
 			bouncing: ProjectileType::Bouncing {
 				damage: 40.,
 				speed: 500.,
 				multishot: 2,
 				bounce_limit: 4,
+				status: Some(StatusApplication {
+					kind: StatusKind::Slow,
+					stacks: 1,
+					duration: 2.,
+				}),
+				fragmentation: Fragmentation::default(),
 			},
 			grenade: ProjectileType::Grenade {
 				damage: 100.,
@@ -42,28 +113,51 @@ impl Plugin for PlayerPlugin {
 				drag: 3.4,
 				explosive_range: 100.,
 				explosive_speed: 300.,
+				status: Some(StatusApplication {
+					kind: StatusKind::Freeze,
+					stacks: 1,
+					duration: 1.5,
+				}),
+				payload: None,
+				fragmentation: Fragmentation::default(),
+			},
+			homing: ProjectileType::Homing {
+				damage: 30.,
+				speed: 350.,
+				multishot: 2,
+				turn_rate: 180.,
+				range: 300.,
+				status: None,
+				fragmentation: Fragmentation::default(),
 			},
 			piercing: ProjectileType::Piercing {
 				damage: 20.,
 				speed: 500.,
 				multishot: 1,
 				penetration: 5,
+				status: Some(StatusApplication {
+					kind: StatusKind::Poison,
+					stacks: 1,
+					duration: 4.,
+				}),
+				fragmentation: Fragmentation::default(),
 			},
 		});
 
 		app.add_systems(Update, spawn_player.in_set(GameStartSystems));
 		app.add_systems(
-			Update,
-			(
-				player_movement,
-				look_at_mouse,
-				fire_projectile,
-				change_projectile,
-				health_regen,
-			)
+			FixedPreUpdate,
+			(player_aim, player_fire, player_select, player_switch_weapon)
+				.after(update_intent)
 				.in_set(GameplaySystems),
 		);
-		app.add_systems(PostUpdate, gameover_transition.in_set(GameplaySystems));
+		app.add_systems(
+			FixedUpdate,
+			(player_movement, player_dash, fade_afterimages)
+				.chain()
+				.in_set(GameplaySystems),
+		);
+		app.add_systems(FixedPostUpdate, gameover_transition.in_set(GameplaySystems));
 		// #[cfg(debug_assertions)]
 		// app.add_systems(PostUpdate, infinite_health.in_set(GameplaySystems));
 	}
@@ -85,19 +179,11 @@ fn infinite_health(player: Single<(&mut Health, &mut Life, &MaxHealth), With<Pla
 	life.1 = true;
 }
 
-fn health_regen(player: Single<(&mut Health, &Life, &MaxHealth), With<Player>>, time: Res<Time>) {
-	let (mut health, life, max) = player.into_inner();
-	if life.is_dead() {
-		return;
-	}
-	health.0 += max.0 * 0.01 * time.delta_secs();
-}
-
 fn spawn_player(
 	mut commands: Commands,
 	mut meshes: ResMut<Assets<Mesh>>,
 	mut materials: ResMut<Assets<ColorMaterial>>,
-	window: Single<&Window, With<PrimaryWindow>>,
+	arena: Res<ArenaSize>,
 	default_proj_types: Res<DefaultProjTypes>,
 	fonts: Res<Fonts>,
 ) {
@@ -105,6 +191,7 @@ fn spawn_player(
 		.spawn((
 			Player,
 			Weapon::Auto,
+			WeaponInventory::new(WEAPON_SLOTS, Weapon::Auto),
 			default_proj_types.basic,
 			RigidBody::Dynamic,
 			ActiveEvents::COLLISION_EVENTS,
@@ -115,7 +202,20 @@ fn spawn_player(
 				linear_damping: 1.,
 				..default()
 			},
-			MaxHealth(1000.),
+			Velocity::zero(),
+			(
+				MaxHealth(1000.),
+				HealthRegen(10.),
+				IFrames::new(0.5),
+				BaseStats(StatBlock {
+					max_health: 1000.,
+					//1% of max health per second
+					regen: 10.,
+					move_speed: 100.,
+					crit_chance: 0.05,
+					..default()
+				}),
+			),
 			MeshMaterial2d(materials.add(Color::linear_rgb(1.0, 0.0, 0.39))),
 			children![(
 				Transform::from_translation(Vec3::Y * 7.),
@@ -125,8 +225,11 @@ fn spawn_player(
 			CollisionGroups::new(PLAYER_GROUP, Group::ALL),
 		))
 		.id();
+	commands.insert_resource(AfterimageMaterial(
+		materials.add(Color::linear_rgba(1.0, 0.0, 0.39, 0.35)),
+	));
 
-	let size = window.size();
+	let size = arena.0;
 	const HEALTH_SIZE: Vec2 = Vec2::new(300., 20.);
 	let pos = Vec3::new(0., (size.y / -2.) + 50., 10.);
 	commands.spawn((
@@ -161,66 +264,129 @@ fn spawn_player(
 		MeshMaterial2d(materials.add(Color::linear_rgb(1.0, 0.0, 0.0))),
 		Cleanable,
 	));
+
+	const DASH_SIZE: Vec2 = Vec2::new(300., 6.);
+	let pos = pos - Vec3::Y * (HEALTH_SIZE.y + DASH_SIZE.y) * 0.5 - Vec3::Y * 2.;
+	commands.spawn((
+		Name::new("Dash Bar"),
+		Transform::from_translation(pos),
+		DashBar(player),
+		Mesh2d(meshes.add(Rectangle::from_size(DASH_SIZE))),
+		MeshMaterial2d(materials.add(Color::linear_rgb(0.3, 0.8, 1.0))),
+		Cleanable,
+	));
 }
 
-fn player_movement(
-	player: Single<(&MoveSpeed, &Life, &mut ExternalForce), With<Player>>,
-	key: Res<ButtonInput<KeyCode>>,
-) {
-	let (move_speed, life, mut force) = player.into_inner();
+fn player_movement(player: Single<(&MoveSpeed, &Life, &PlayerIntent, &mut ExternalForce), With<Player>>) {
+	let (move_speed, life, intent, mut force) = player.into_inner();
 	if life.is_dead() {
 		return;
 	}
-	let mut move_dir = Vec2::ZERO;
+	force.force = intent.move_dir * move_speed.0 * 500.;
+}
 
-	if key.pressed(KeyCode::KeyW) {
-		move_dir.y = 1.0;
-	} else if key.pressed(KeyCode::KeyS) {
-		move_dir.y = -1.0;
+fn player_dash(
+	player: Single<
+		(
+			&mut Dash,
+			&mut Velocity,
+			&mut IFrames,
+			&Stats,
+			&MoveSpeed,
+			&PlayerIntent,
+			&Life,
+			&Transform,
+			&Mesh2d,
+		),
+		With<Player>,
+	>,
+	afterimage: Res<AfterimageMaterial>,
+	audio: Res<AudioClips>,
+	time: Res<Time>,
+	mut commands: Commands,
+) {
+	let (mut dash, mut vel, mut iframes, stats, move_speed, intent, life, transform, mesh) = player.into_inner();
+	if life.is_dead() {
+		return;
+	}
+	dash.cooldown.tick(time.delta());
+	if intent.dash && dash.cooldown.finished() && !dash.is_dashing() {
+		//Dashes where the player is heading, or where they aim when standing still
+		dash.dir = intent.move_dir.try_normalize().unwrap_or(intent.aim_dir);
+		dash.active.reset();
+		dash.cooldown = Timer::from_seconds(stats.0.dash_cooldown, TimerMode::Once);
+		iframes.grant(DASH_TIME);
+		play_audio_onshot(&mut commands, audio.dash.clone());
+	}
+	if !dash.is_dashing() {
+		return;
+	}
+	dash.active.tick(time.delta());
+	if dash.active.just_finished() {
+		//Drop back to running speed instead of sliding on with the burst
+		vel.linvel = dash.dir * move_speed.0;
+		return;
 	}
+	vel.linvel = dash.dir * stats.0.dash_distance / DASH_TIME;
+	commands.spawn((
+		Name::new("Afterimage"),
+		Afterimage,
+		Lifetime::new(AFTERIMAGE_TIME),
+		Transform::from_translation(transform.translation - Vec3::Z),
+		mesh.clone(),
+		MeshMaterial2d(afterimage.0.clone()),
+		Cleanable,
+	));
+}
 
-	if key.pressed(KeyCode::KeyD) {
-		move_dir.x = 1.0;
-	} else if key.pressed(KeyCode::KeyA) {
-		move_dir.x = -1.0;
+fn fade_afterimages(query: Query<(&mut Transform, &Lifetime), With<Afterimage>>) {
+	for (mut transform, lifetime) in query {
+		transform.scale = Vec3::splat(lifetime.0.fraction_remaining());
 	}
+}
 
-	force.force = move_dir.normalize_or_zero() * move_speed.0 * 500.;
+fn player_aim(player: Single<(&mut Transform, &PlayerIntent), With<Player>>) {
+	let (mut transform, intent) = player.into_inner();
+	transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, intent.aim_dir);
 }
 
-fn look_at_mouse(
-	mut player: Single<&mut Transform, With<Player>>,
-	cam: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
-	window: Single<&Window, With<PrimaryWindow>>,
-) {
-	let (cam, cam_transform) = cam.into_inner();
-	if let Some(m_pos) = window.cursor_position() {
-		if let Ok(world_pos) = cam.viewport_to_world_2d(cam_transform, m_pos) {
-			let dir = (player.translation.xy() - world_pos).normalize();
+fn player_fire(player: Single<(&mut WeaponFiring, &PlayerIntent), With<Player>>) {
+	let (mut firing, intent) = player.into_inner();
+	firing.0 = intent.fire;
+}
 
-			let rot = Quat::from_rotation_arc_2d(Vec2::NEG_Y, dir);
-			player.rotation = rot;
+//Also keeps `Weapon` in step with the inventory when a pickup changes the weapon in use
+fn player_switch_weapon(
+	player: Single<(&mut Weapon, &mut WeaponInventory, &PlayerIntent), With<Player>>,
+	audio: Res<AudioClips>,
+	mut commands: Commands,
+) {
+	let (mut weapon, mut inventory, intent) = player.into_inner();
+	if let Some(slot) = intent.weapon {
+		inventory.select(slot);
+	}
+	if intent.next_weapon {
+		inventory.cycle();
+	}
+	if *weapon != inventory.current() {
+		if intent.weapon.is_some() || intent.next_weapon {
+			play_audio_onshot(&mut commands, audio.weapon_switch.clone());
 		}
+		*weapon = inventory.current();
 	}
 }
 
-fn fire_projectile(mut player_firing: Single<&mut WeaponFiring, With<Player>>, mouse: Res<ButtonInput<MouseButton>>) {
-	player_firing.0 = mouse.pressed(MouseButton::Left);
-}
-
-fn change_projectile(
-	player: Single<&mut ProjectileType, With<Player>>,
-	key: Res<ButtonInput<KeyCode>>,
+fn player_select(
+	player: Single<(&mut ProjectileType, &PlayerIntent), With<Player>>,
 	default_proj_types: Res<DefaultProjTypes>,
 ) {
-	let mut proj_type = player.into_inner();
-	if key.just_pressed(KeyCode::Digit1) {
-		*proj_type = default_proj_types.basic;
-	} else if key.just_pressed(KeyCode::Digit2) {
-		*proj_type = default_proj_types.piercing;
-	} else if key.just_pressed(KeyCode::Digit3) {
-		*proj_type = default_proj_types.bouncing;
-	} else if key.just_pressed(KeyCode::Digit4) {
-		*proj_type = default_proj_types.grenade;
+	let (mut proj_type, intent) = player.into_inner();
+	match intent.select {
+		Some(1) => *proj_type = default_proj_types.basic,
+		Some(2) => *proj_type = default_proj_types.piercing,
+		Some(3) => *proj_type = default_proj_types.bouncing,
+		Some(4) => *proj_type = default_proj_types.grenade,
+		Some(5) => *proj_type = default_proj_types.homing,
+		_ => {}
 	}
 }
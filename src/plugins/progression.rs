@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+	components::{
+		input::PlayerIntent,
+		stats::{Experience, StatModifier, StatModifiers},
+		utils::Cleanable,
+		weapons::{ProjectileType, ScatterOnHit},
+	},
+	plugins::{input::update_intent, player::Player, utils::play_audio_onshot},
+	resources::{
+		audio::AudioClips,
+		input::{Action, ActionMap},
+		upgrades::{LevelUpDraft, UpgradeDefinition, UpgradeEffect, UpgradePool, UpgradePoolHandle, UpgradePoolLoader},
+		utils::{DefaultProjTypes, Fonts, RandomGen},
+	},
+	state_management::{GameStartSystems, GameplaySystems, PlayerInputSystems, RngSystems},
+};
+
+const UPGRADE_POOL_PATH: &str = "upgrades/default.upgrades.ron";
+const DRAFT_SIZE: usize = 3;
+
+pub struct ProgressionPlugin;
+
+impl Plugin for ProgressionPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_asset::<UpgradePool>();
+		app.init_asset_loader::<UpgradePoolLoader>();
+		app.init_resource::<LevelUpDraft>();
+		app.add_systems(Startup, load_pool);
+		app.add_systems(Update, reset_draft.in_set(GameStartSystems));
+		app.add_systems(
+			FixedPostUpdate,
+			open_draft.in_set(GameplaySystems).in_set(RngSystems::Drafts),
+		);
+		app.add_systems(
+			FixedPreUpdate,
+			pick_upgrade.after(update_intent).in_set(PlayerInputSystems),
+		);
+	}
+}
+
+pub fn draft_closed(draft: Res<LevelUpDraft>) -> bool {
+	!draft.is_open()
+}
+
+#[derive(Component)]
+struct DraftUI;
+
+fn load_pool(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(UpgradePoolHandle(asset_server.load(UPGRADE_POOL_PATH)));
+}
+
+fn reset_draft(mut draft: ResMut<LevelUpDraft>, mut physics: Single<&mut RapierConfiguration>) {
+	*draft = LevelUpDraft::default();
+	physics.physics_pipeline_active = true;
+}
+
+//Rolls the choices for the next pending level and pauses physics until one is picked
+fn open_draft(
+	mut draft: ResMut<LevelUpDraft>,
+	pool: Res<UpgradePoolHandle>,
+	pools: Res<Assets<UpgradePool>>,
+	mut rng: ResMut<RandomGen>,
+	mut physics: Single<&mut RapierConfiguration>,
+	player: Single<&Experience, With<Player>>,
+	fonts: Res<Fonts>,
+	actions: Res<ActionMap>,
+	audio: Res<AudioClips>,
+	mut commands: Commands,
+) {
+	if draft.pending == 0 {
+		return;
+	}
+	let Some(pool) = pools.get(&pool.0) else {
+		return;
+	};
+	let mut candidates: Vec<usize> = (0..pool.upgrades.len())
+		.filter(|i| {
+			let max_picks = pool.upgrades[*i].max_picks;
+			max_picks == 0 || draft.picks.get(i).copied().unwrap_or(0) < max_picks
+		})
+		.collect();
+	let mut choices = Vec::new();
+	while choices.len() < DRAFT_SIZE {
+		let weights = candidates.iter().map(|i| pool.upgrades[*i].rarity.weight());
+		let Some(choice) = rng.weighted_index(weights) else {
+			break;
+		};
+		choices.push(candidates.remove(choice));
+	}
+	if choices.is_empty() {
+		//Everything has been maxed out
+		draft.pending = 0;
+		return;
+	}
+	physics.physics_pipeline_active = false;
+	play_audio_onshot(&mut commands, audio.pickup.clone());
+	spawn_draft_ui(&mut commands, &fonts, &actions, player.level, &choices, pool);
+	draft.choices = choices;
+}
+
+fn spawn_draft_ui(
+	commands: &mut Commands,
+	fonts: &Fonts,
+	actions: &ActionMap,
+	level: u32,
+	choices: &[usize],
+	pool: &UpgradePool,
+) {
+	commands.spawn((
+		DraftUI,
+		Cleanable,
+		Transform::from_xyz(0.0, 120., 20.0),
+		Text2d::new(format!("Level {level}")),
+		TextFont {
+			font: fonts.noto_thin.clone(),
+			font_size: 60.,
+			..default()
+		},
+		TextLayout::new_with_justify(JustifyText::Center),
+	));
+	for (i, choice) in choices.iter().enumerate() {
+		let upgrade = &pool.upgrades[*choice];
+		let label = actions.label(Action::PickUpgrade(i as u8 + 1));
+		commands.spawn((
+			DraftUI,
+			Cleanable,
+			Transform::from_xyz(0.0, 40. - 70. * i as f32, 20.0),
+			Text2d::new(format!(
+				"[{label}] {} ({})\n{}",
+				upgrade.name,
+				upgrade.rarity.label(),
+				upgrade.description()
+			)),
+			TextFont {
+				font: fonts.noto_regular.clone(),
+				font_size: 20.,
+				..default()
+			},
+			TextColor(upgrade.rarity.color()),
+			TextLayout::new_with_justify(JustifyText::Center),
+		));
+	}
+}
+
+fn pick_upgrade(
+	mut draft: ResMut<LevelUpDraft>,
+	pool: Res<UpgradePoolHandle>,
+	pools: Res<Assets<UpgradePool>>,
+	player: Single<
+		(
+			&PlayerIntent,
+			&mut StatModifiers,
+			&mut ProjectileType,
+			&mut ScatterOnHit,
+		),
+		With<Player>,
+	>,
+	mut projectiles: ResMut<DefaultProjTypes>,
+	mut physics: Single<&mut RapierConfiguration>,
+	ui: Query<Entity, With<DraftUI>>,
+	mut commands: Commands,
+) {
+	if !draft.is_open() {
+		return;
+	}
+	let (intent, mut modifiers, mut proj_type, mut scatter) = player.into_inner();
+	let Some(choice) = intent
+		.pick
+		.and_then(|pick| draft.choices.get(pick as usize - 1).copied())
+	else {
+		return;
+	};
+	let Some(pool) = pools.get(&pool.0) else {
+		return;
+	};
+	apply_upgrade(&pool.upgrades[choice], &mut modifiers, &mut projectiles, &mut scatter);
+	*proj_type = projectiles.matching(&proj_type);
+	*draft.picks.entry(choice).or_default() += 1;
+	draft.choices.clear();
+	draft.pending -= 1;
+	physics.physics_pipeline_active = true;
+	for entity in ui {
+		commands.entity(entity).despawn();
+	}
+}
+
+fn apply_upgrade(
+	upgrade: &UpgradeDefinition,
+	modifiers: &mut StatModifiers,
+	projectiles: &mut DefaultProjTypes,
+	scatter: &mut ScatterOnHit,
+) {
+	for effect in &upgrade.effects {
+		match *effect {
+			UpgradeEffect::Stat { stat, op } => {
+				modifiers.add(StatModifier::permanent(stat, op, upgrade.name.clone()));
+			}
+			UpgradeEffect::Multishot(count) => {
+				for proj_type in projectiles.all_mut() {
+					*proj_type.multishot_mut() += count;
+				}
+			}
+			UpgradeEffect::ProjectileStatus { projectile, status } => {
+				*projectiles.get_mut(projectile).status_mut() = Some(status);
+			}
+			UpgradeEffect::ScatterOnHit { chance, damage, range } => {
+				scatter.chance = (scatter.chance + chance).min(1.);
+				scatter.damage += damage;
+				scatter.range = scatter.range.max(range);
+			}
+			UpgradeEffect::ProjectileFragmentation {
+				projectile,
+				fragmentation,
+			} => {
+				*projectiles.get_mut(projectile).fragmentation_mut() = fragmentation;
+			}
+			UpgradeEffect::GrenadePayload(stage) => {
+				if let ProjectileType::Grenade { payload, .. } = &mut projectiles.grenade {
+					*payload = Some(stage);
+				}
+			}
+		}
+	}
+}
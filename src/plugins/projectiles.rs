@@ -3,22 +3,35 @@ use bevy_rapier2d::prelude::*;
 
 use crate::{
 	components::{
-		stats::{Damage, Health, Life},
-		tags::{ContactLimit, Owner, Projectile},
+		death::{ChainLink, DeathScatter, ScatterOwner, ScatterPattern, ScatterStage},
+		stats::{Critical, Damage, Knockback, Life},
+		status::InflictStatus,
+		tags::{ContactLimit, Enemy, Owner, Projectile},
+		utils::{Cleanable, Lifetime},
+		weapons::{Fragment, Fragmentation, Homing, ScatterOnHit},
 	},
-	plugins::{player::Player, utils::play_audio_onshot},
-	resources::audio::AudioClips,
-	state_management::{GameOverSystems, GameplaySystems},
+	plugins::{player::Player, utils::started_collision},
+	resources::utils::{ChainStats, DamageEvent, DamageKind, RandomGen},
+	state_management::{GameOverSystems, GameplaySystems, RngSystems},
 };
 
+const FRAGMENT_SIZE: f32 = 1.;
+const FRAGMENT_LIFETIME: f32 = 0.75;
+
 pub struct ProjectilesPlugin;
 
 impl Plugin for ProjectilesPlugin {
 	fn build(&self, app: &mut App) {
 		app.register_type::<Projectiles>();
 		app.add_systems(Startup, init_meshes);
-		app.add_systems(Update, handle_projectile_collisions.in_set(GameplaySystems));
-		app.add_systems(Update, handle_projectile_collisions.in_set(GameOverSystems));
+		app.add_systems(
+			FixedUpdate,
+			(handle_projectile_collisions.in_set(RngSystems::Hits), steer_homing).in_set(GameplaySystems),
+		);
+		app.add_systems(
+			FixedUpdate,
+			(handle_projectile_collisions.in_set(RngSystems::Hits), steer_homing).in_set(GameOverSystems),
+		);
 		app.add_systems(PostUpdate, init_projectiles.in_set(GameplaySystems));
 		app.add_systems(PostUpdate, init_projectiles.in_set(GameOverSystems));
 	}
@@ -27,12 +40,16 @@ impl Plugin for ProjectilesPlugin {
 struct Projectiles {
 	mesh: Handle<Mesh>,
 	mat: Handle<ColorMaterial>,
+	enemy_mesh: Handle<Mesh>,
+	enemy_mat: Handle<ColorMaterial>,
 }
 
 fn init_meshes(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>) {
 	commands.insert_resource(Projectiles {
 		mesh: meshes.add(Circle::new(2.)),
 		mat: materials.add(Color::linear_rgb(1.0, 0.6, 0.16)),
+		enemy_mesh: meshes.add(Circle::new(3.)),
+		enemy_mat: materials.add(Color::linear_rgb(2.0, 0.1, 0.8)),
 	});
 }
 fn init_projectiles(
@@ -47,48 +64,158 @@ fn init_projectiles(
 					.entity(entity)
 					.insert((Mesh2d(proj_mesh.mesh.clone()), MeshMaterial2d(proj_mesh.mat.clone())));
 			}
-			Owner::Enemy => (),
+			Owner::Enemy => {
+				commands.entity(entity).insert((
+					Mesh2d(proj_mesh.enemy_mesh.clone()),
+					MeshMaterial2d(proj_mesh.enemy_mat.clone()),
+				));
+			}
 		}
 	}
 }
 
 fn handle_projectile_collisions(
-	mut projectiles: Query<(Entity, &Damage, &mut ContactLimit, &Projectile)>,
-	mut targets: Query<(&mut Health, &mut Life, Option<&Player>)>,
+	mut projectiles: Query<(
+		&Damage,
+		&mut ContactLimit,
+		&Projectile,
+		&Transform,
+		Option<&Critical>,
+		Option<&Knockback>,
+		Option<&Fragmentation>,
+		Option<&Fragment>,
+	)>,
+	fragment_data: Query<(&Velocity, &CollisionGroups, Option<&InflictStatus>)>,
+	enemies: Query<(), With<Enemy>>,
+	scatter: Query<&ScatterOnHit, With<Player>>,
 	mut collision_events: EventReader<CollisionEvent>,
+	mut damage_events: EventWriter<DamageEvent>,
+	mut rng: ResMut<RandomGen>,
+	mut chains: ResMut<ChainStats>,
 	mut commands: Commands,
-	audio: Res<AudioClips>,
 ) {
+	let scatter = scatter.single().ok().filter(|scatter| scatter.chance > 0.);
 	for event in collision_events.read() {
-		if let CollisionEvent::Started(entity_a, entity_b, _) = event {
-			if let Ok((e, damage, mut contacts, proj)) = projectiles.get_mut(*entity_a) {
-				if let Ok((mut health, mut life, player)) = targets.get_mut(*entity_b) {
-					play_sounds(&audio, &mut commands, player.is_some(), proj.0);
-					apply_damage(&mut health, &mut life, damage);
-				}
-				process_contacts(&mut contacts, e, &mut commands);
-			} else if let Ok((e, damage, mut contacts, proj)) = projectiles.get_mut(*entity_b) {
-				if let Ok((mut health, mut life, player)) = targets.get_mut(*entity_a) {
-					play_sounds(&audio, &mut commands, player.is_some(), proj.0);
-					apply_damage(&mut health, &mut life, damage);
-				}
-				process_contacts(&mut contacts, e, &mut commands);
+		if let Some((e, other)) = started_collision(event, |e| projectiles.contains(e))
+			&& let Ok((damage, mut contacts, proj, transform, crit, knockback, fragmentation, fragment)) =
+				projectiles.get_mut(e)
+		{
+			if fragment.is_some_and(|fragment| fragment.ignore == other) {
+				continue;
+			}
+			if let (Some(scatter), Owner::Player) = (scatter, proj.0)
+				&& enemies.contains(other)
+				&& rng.chance(scatter.chance)
+			{
+				spawn_hit_scatter(&mut commands, scatter, transform.translation, chains.start_chain());
 			}
+			damage_events.write(DamageEvent {
+				source: e,
+				target: other,
+				amount: damage.0,
+				kind: DamageKind::Kinetic,
+				crit: crit.copied().unwrap_or_default(),
+				origin: transform.translation.xy(),
+				knockback: knockback.map_or(0., |knockback| knockback.0),
+				instigator: proj.0,
+			});
+			let generation = fragment.map_or(0, |fragment| fragment.generation);
+			if let (Some(fragmentation), Ok((vel, groups, status))) = (fragmentation, fragment_data.get(e))
+				&& fragmentation.splits(generation)
+			{
+				let fragment = Fragment {
+					ignore: other,
+					generation: generation + 1,
+				};
+				let damage = damage.0 * fragmentation.damage;
+				let knockback = knockback.map_or(0., |knockback| knockback.0);
+				spawn_fragments(
+					&mut commands,
+					fragmentation,
+					fragment,
+					proj,
+					transform.translation,
+					vel.linvel,
+					*groups,
+					damage,
+					knockback,
+					crit,
+					status,
+				);
+			}
+			process_contacts(&mut contacts, e, &mut commands);
 		}
 	}
 }
-pub fn play_sounds(audio: &AudioClips, commands: &mut Commands, is_player: bool, owner: Owner) {
-	if let Owner::Enemy = owner {
-		if !is_player {
-			return;
+
+//Left for the death scatter systems to set off, the same way a grenade goes off
+//Each starts a chain of its own, so its kills count the way a dead enemy's scatter kills do
+fn spawn_hit_scatter(commands: &mut Commands, scatter: &ScatterOnHit, pos: Vec3, link: ChainLink) {
+	commands.spawn((
+		Name::new("Hit Scatter"),
+		DeathScatter::from(ScatterStage {
+			count: 1,
+			pattern: ScatterPattern::Explosion {
+				range: scatter.range,
+				speed: scatter.range * 3.,
+			},
+			damage: scatter.damage,
+			..default()
+		}),
+		ScatterOwner(Owner::Player),
+		link,
+		Life(false, true),
+		Transform::from_translation(pos),
+		Cleanable,
+	));
+}
+
+//Fanned out around the direction the projectile was going, carrying on with its speed and everything it inflicts
+fn spawn_fragments(
+	commands: &mut Commands,
+	fragmentation: &Fragmentation,
+	fragment: Fragment,
+	proj: &Projectile,
+	pos: Vec3,
+	vel: Vec2,
+	groups: CollisionGroups,
+	damage: f32,
+	knockback: f32,
+	crit: Option<&Critical>,
+	status: Option<&InflictStatus>,
+) {
+	let speed = vel.length();
+	let aim = vel.normalize_or(Vec2::Y);
+	let interval = fragmentation.arc / fragmentation.count as f32;
+	for i in 0..fragmentation.count {
+		let angle = (i as f32 + 0.5) * interval - fragmentation.arc / 2.;
+		let dir = Vec2::from_angle(angle.to_radians()).rotate(aim);
+		let entity = commands
+			.spawn((
+				Name::new("Fragment"),
+				Projectile(proj.0),
+				fragment,
+				*fragmentation,
+				Damage(damage),
+				Knockback(knockback),
+				Transform::from_translation(pos),
+				RigidBody::Dynamic,
+				Velocity::linear(dir * speed),
+				Collider::ball(FRAGMENT_SIZE),
+				Sensor,
+				groups,
+				ActiveEvents::COLLISION_EVENTS,
+				ContactLimit(1),
+				Lifetime::new(FRAGMENT_LIFETIME),
+			))
+			.id();
+		if let Some(crit) = crit {
+			commands.entity(entity).insert(*crit);
+		}
+		if let Some(status) = status {
+			commands.entity(entity).insert(InflictStatus(status.0));
 		}
 	}
-	let clip = if is_player {
-		audio.hurt.clone()
-	} else {
-		audio.hit.clone()
-	};
-	play_audio_onshot(commands, clip);
 }
 
 pub fn process_contacts(contacts: &mut ContactLimit, entity: Entity, commands: &mut Commands) {
@@ -100,9 +227,44 @@ pub fn process_contacts(contacts: &mut ContactLimit, entity: Entity, commands: &
 	}
 }
 
-pub fn apply_damage(health: &mut Health, life: &mut Life, damage: &Damage) {
-	health.0 -= damage.0;
-	if health.0 <= 0. {
-		life.0 = false;
+//Keeps its speed while turning toward the target, only reaching for a new one when the old one dies
+fn steer_homing(
+	projectiles: Query<(&mut Homing, &mut Velocity, &Transform)>,
+	targets: Query<(Entity, &Transform, &Life, Has<Player>), Or<(With<Enemy>, With<Player>)>>,
+	time: Res<Time>,
+) {
+	for (mut homing, mut vel, transform) in projectiles {
+		let pos = transform.translation.xy();
+		let current = homing
+			.target
+			.and_then(|target| targets.get(target).ok())
+			.filter(|(_, _, life, _)| life.is_alive())
+			.map(|(_, target, ..)| target.translation.xy());
+		let target = match current {
+			Some(target) => target,
+			None => {
+				let range_sq = homing.range * homing.range;
+				let hunts_player = matches!(homing.owner, Owner::Enemy);
+				let nearest = targets
+					.iter()
+					.filter(|(_, _, life, is_player)| life.is_alive() && *is_player == hunts_player)
+					.map(|(entity, target, ..)| (entity, target.translation.xy()))
+					.filter(|(_, target)| target.distance_squared(pos) <= range_sq)
+					.min_by(|(_, a), (_, b)| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+				homing.target = nearest.map(|(entity, _)| entity);
+				let Some((_, target)) = nearest else {
+					continue;
+				};
+				target
+			}
+		};
+		let Some(dir) = vel.linvel.try_normalize() else {
+			continue;
+		};
+		let max_turn = homing.turn_rate.to_radians() * time.delta_secs();
+		let turn = dir
+			.angle_to((target - pos).normalize_or(dir))
+			.clamp(-max_turn, max_turn);
+		vel.linvel = Vec2::from_angle(turn).rotate(vel.linvel);
 	}
 }
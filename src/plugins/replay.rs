@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::{
+	resources::{
+		input::TickInput,
+		replay::{Replay, ReplayPlayback, ReplayRecorder},
+		utils::RunSeed,
+	},
+	state_management::{GameStartSystems, GameplayState, InputSourceSystems, PlayerInputSystems},
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ReplayRecorder>();
+		app.add_systems(PreUpdate, start_recording.in_set(GameStartSystems));
+		app.add_systems(
+			FixedPreUpdate,
+			record_tick.after(InputSourceSystems).in_set(PlayerInputSystems),
+		);
+		app.add_systems(OnEnter(GameplayState::GameOver), save_recording);
+	}
+}
+
+fn start_recording(mut recorder: ResMut<ReplayRecorder>, seed: Res<RunSeed>) {
+	recorder.replay = Replay {
+		seed: seed.seed,
+		frames: Vec::new(),
+	};
+}
+
+//Every tick is recorded, whichever source the input came from
+fn record_tick(tick: Res<TickInput>, mut recorder: ResMut<ReplayRecorder>) {
+	recorder.replay.frames.push(tick.0);
+}
+
+fn save_recording(recorder: Res<ReplayRecorder>, playback: Option<Res<ReplayPlayback>>) {
+	if playback.is_some() {
+		return;
+	}
+	let Some(path) = &recorder.path else {
+		return;
+	};
+	match recorder.replay.save(path) {
+		Ok(()) => info!("Saved replay to {path}"),
+		Err(err) => error!("Failed to save replay to {path}: {err}"),
+	}
+}
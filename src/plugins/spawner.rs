@@ -1,161 +1,170 @@
-use std::time::Duration;
-
 use crate::{
-	components::{spawner::SpawnBatch, stats::MaxHealth, utils::Cleanable},
-	resources::utils::RandomGen,
-	state_management::{GameStartSystems, GameplaySystems},
+	components::{
+		archetype::{EnemyArchetype, EnemyBody},
+		spawner::SpawnBatch,
+	},
+	plugins::elites::roll_elite,
+	resources::{
+		enemies::{EnemyPrefabs, EnemyRoster, EnemyRosterHandle, EnemyRosterLoader},
+		utils::RandomGen,
+		waves::WaveDirector,
+	},
+	state_management::{GameplaySystems, RngSystems},
+};
+use bevy::{
+	ecs::{entity_disabling::Disabled, reflect::ReflectCommandExt},
+	gizmos::GizmoPlugin,
+	prelude::*,
 };
-use bevy::{ecs::entity_disabling::Disabled, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
 	ENEMY_GROUP,
-	components::{
-		ai::{ChargeAI, ChaseAI, HoverAI},
-		death::{DeathScatter, ScatterPattern, Targeting},
-		spawner::Spawner,
-		stats::MoveSpeedStat,
-		tags::Enemy,
-	},
+	components::{spawner::Spawner, tags::Enemy},
 };
 
-const SPAWNER_COUNT: usize = 3;
-const SPAWNER_ANGLE: f32 = 360. / SPAWNER_COUNT as f32;
+const ROSTER_PATH: &str = "enemies/enemies.roster.ron";
 
 pub struct EnemySpawnerPlugin;
 
 impl Plugin for EnemySpawnerPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(Startup, prepare_prefabs);
-		app.add_systems(Update, create_spawners.in_set(GameStartSystems));
-		app.add_systems(Update, (spawners_batching, spawners_spawning).in_set(GameplaySystems));
+		app.init_asset::<EnemyRoster>();
+		app.init_asset_loader::<EnemyRosterLoader>();
+		app.init_resource::<EnemyPrefabs>();
+		app.add_systems(Startup, load_roster);
+		app.add_systems(Update, build_prefabs);
+		app.add_systems(
+			FixedUpdate,
+			(spawners_batching, spawners_spawning)
+				.chain()
+				.in_set(GameplaySystems)
+				.in_set(RngSystems::Spawning),
+		);
 		#[cfg(debug_assertions)]
-		app.add_systems(Update, spawner_viz);
+		if app.is_plugin_added::<GizmoPlugin>() {
+			app.add_systems(Update, spawner_viz);
+		}
 	}
 }
 
-#[derive(Resource, Reflect)]
-#[reflect(Resource)]
-struct Prefabs {
-	pub charger: Entity,
-	pub hover: Entity,
-	pub chaser: Entity,
+fn load_roster(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(EnemyRosterHandle(asset_server.load(ROSTER_PATH)));
 }
 
-fn prepare_prefabs(
+//Builds or rebuilds the prefab of every enemy definition in the roster whenever it (re)loads
+fn build_prefabs(
+	mut roster_events: EventReader<AssetEvent<EnemyRoster>>,
+	mut scene_events: EventReader<AssetEvent<DynamicScene>>,
+	roster: Res<EnemyRosterHandle>,
+	rosters: Res<Assets<EnemyRoster>>,
+	scenes: Res<Assets<DynamicScene>>,
+	asset_server: Res<AssetServer>,
+	registry: Res<AppTypeRegistry>,
+	mut prefabs: ResMut<EnemyPrefabs>,
 	mut commands: Commands,
 	mut materials: ResMut<Assets<ColorMaterial>>,
 	mut meshes: ResMut<Assets<Mesh>>,
 ) {
-	let charger = commands
-		.spawn((
-			Enemy,
-			Name::new("Charger"),
-			ChargeAI {
-				distance: 200.,
-				speed_multi: 20.,
-				hit_damage: 70.,
-			},
-			ActiveEvents::COLLISION_EVENTS,
-			CollisionGroups::new(ENEMY_GROUP, Group::ALL),
-			MaxHealth(100.),
-			Mesh2d(meshes.add(Capsule2d::new(5.0, 10.0))),
-			MeshMaterial2d(materials.add(Color::linear_rgb(1.0, 1.0, 0.0))),
-			RigidBody::Dynamic,
-			Damping {
-				linear_damping: 1.,
-				..default()
-			},
-			MoveSpeedStat(30.),
-			Collider::ball(4.),
-			DeathScatter {
-				count: 20,
-				pattern: ScatterPattern::Spread {
-					arc: 30.,
-					targeting: Targeting::Forward,
-				},
-				damage: 20.,
-			},
-			Disabled,
-		))
-		.id();
-
-	let hover = commands
-		.spawn((
+	let roster_changed = roster_events
+		.read()
+		.any(|event| event.is_loaded_with_dependencies(&roster.0) || event.is_modified(&roster.0));
+	//Definitions still loading when the roster arrived get built once they finish
+	let changed: Vec<_> = scene_events
+		.read()
+		.filter_map(|event| match event {
+			AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+			_ => None,
+		})
+		.collect();
+	if !roster_changed && changed.is_empty() {
+		return;
+	}
+	let Some(roster) = rosters.get(&roster.0) else {
+		return;
+	};
+
+	let registry = registry.read();
+	let mut archetypes = Vec::new();
+	for handle in &roster.enemies {
+		let Some(archetype) = archetype_name(&asset_server, handle) else {
+			continue;
+		};
+		archetypes.push(archetype.clone());
+		let existing = prefabs.0.get(&archetype).copied();
+		if existing.is_some() && !changed.contains(&handle.id()) {
+			continue;
+		}
+		let Some(scene) = scenes.get(handle) else {
+			continue;
+		};
+		let Some(definition) = scene.entities.first() else {
+			warn!("Enemy definition {} is empty", archetype);
+			continue;
+		};
+		let prefab = existing.unwrap_or_else(|| commands.spawn_empty().id());
+		let mut entity_commands = commands.entity(prefab);
+		entity_commands.clear().insert((
 			Enemy,
-			HoverAI {
-				hover_distance: 150.,
-				range: 40.,
-			},
-			CollisionGroups::new(ENEMY_GROUP, Group::ALL),
-			MaxHealth(100.),
-			Mesh2d(meshes.add(RegularPolygon::new(5., 6))),
-			MeshMaterial2d(materials.add(Color::linear_rgb(0.0, 1.0, 0.0))),
-			RigidBody::Dynamic,
-			Damping {
-				linear_damping: 1.,
-				..default()
-			},
-			MoveSpeedStat(50.),
-			Collider::ball(4.),
 			Disabled,
-			DeathScatter {
-				count: 40,
-				pattern: ScatterPattern::Spiral { angle: 370., rate: 50. },
-				damage: 10.,
-			},
-		))
-		.id();
-
-	let chaser = commands
-		.spawn((
-			Enemy,
-			ChaseAI,
+			Name::new(archetype.clone()),
+			EnemyArchetype(archetype.clone()),
+			ActiveEvents::COLLISION_EVENTS,
 			CollisionGroups::new(ENEMY_GROUP, Group::ALL),
-			MaxHealth(50.),
-			Mesh2d(meshes.add(Circle::new(5.))),
-			MeshMaterial2d(materials.add(Color::linear_rgb(0.0, 0.0, 1.0))),
 			RigidBody::Dynamic,
+			//Weapons kick back through it
+			Velocity::zero(),
 			Damping {
 				linear_damping: 1.,
 				..default()
 			},
-			MoveSpeedStat(40.),
-			Collider::ball(4.),
-			Disabled,
-			DeathScatter {
-				count: 50,
-				pattern: ScatterPattern::Explosion {
-					range: 100.,
-					speed: 300.,
-				},
-				damage: 40.,
-			},
-		))
-		.id();
+		));
+		for component in &definition.components {
+			if component.represents::<EnemyBody>()
+				&& let Some(body) = EnemyBody::from_reflect(&**component)
+			{
+				entity_commands.insert((
+					Mesh2d(meshes.add(body.shape.mesh())),
+					MeshMaterial2d(materials.add(Color::from(body.color))),
+					Collider::ball(body.collider),
+				));
+			}
+			//Inserting anything that can't be reflected as a component would panic
+			let registered = component
+				.get_represented_type_info()
+				.and_then(|info| registry.get(info.type_id()))
+				.is_some_and(|registration| registration.data::<ReflectComponent>().is_some());
+			if !registered {
+				warn!(
+					"{} in enemy definition {} isn't a registered component",
+					component.reflect_type_path(),
+					archetype
+				);
+				continue;
+			}
+			entity_commands.insert_reflect(component.to_dynamic());
+		}
+		info!("Built enemy prefab {}", archetype);
+		prefabs.0.insert(archetype, prefab);
+	}
 
-	commands.insert_resource(Prefabs { charger, hover, chaser });
+	let removed: Vec<_> = prefabs
+		.0
+		.keys()
+		.filter(|archetype| !archetypes.contains(archetype))
+		.cloned()
+		.collect();
+	for archetype in removed {
+		if let Some(prefab) = prefabs.0.remove(&archetype) {
+			commands.entity(prefab).despawn();
+		}
+	}
 }
 
-fn create_spawners(mut commands: Commands, prefabs: Res<Prefabs>) {
-	for i in 0..SPAWNER_COUNT {
-		let dir = (Vec2::from_angle((i as f32 * SPAWNER_ANGLE).to_radians()) * 400.).extend(0.);
-		let mut timer = Timer::from_seconds(10., TimerMode::Repeating);
-		timer.set_elapsed(Duration::from_secs(8));
-		commands.spawn((
-			Transform::from_translation(dir),
-			Cleanable,
-			Spawner {
-				max_batch_size: 8,
-				min_batch_size: 2,
-				prefabs: vec![prefabs.chaser, prefabs.charger, prefabs.hover],
-				spawn_effect: Entity::PLACEHOLDER,
-				spawn_range: 100.,
-				spawn_rate: timer,
-				spawn_speed: Timer::from_seconds(0.5, TimerMode::Repeating),
-			},
-		));
-	}
+fn archetype_name(asset_server: &AssetServer, handle: &Handle<DynamicScene>) -> Option<String> {
+	let path = asset_server.get_path(handle.id())?;
+	let file_name = path.path().file_name()?.to_str()?;
+	return file_name.split('.').next().map(|name| name.to_string());
 }
 
 #[cfg(debug_assertions)]
@@ -177,27 +186,32 @@ fn spawner_viz(mut gizmos: Gizmos, query: Query<(&Transform, &Spawner, &SpawnBat
 		);
 		gizmos.arc_2d(
 			transform.translation.xy(),
-			spawner.spawn_speed.fraction() * -1. * PI,
+			-spawner.spawn_speed.fraction() * PI,
 			spawner.spawn_range - 2.,
 			LinearRgba::rgb(0.0, 1.0, 1.0),
 		);
 	}
 }
 
-fn spawners_batching(query: Query<(&mut Spawner, &mut SpawnBatch)>, time: Res<Time>, mut rng: ResMut<RandomGen>) {
+fn spawners_batching(
+	query: Query<(&mut Spawner, &mut SpawnBatch)>,
+	time: Res<Time>,
+	mut rng: ResMut<RandomGen>,
+	mut director: ResMut<WaveDirector>,
+) {
 	for (mut spawner, mut batch) in query {
 		spawner.spawn_rate.tick(time.delta());
 		if spawner.spawn_rate.finished() {
-			batch.0 = rng.range(spawner.min_batch_size..spawner.max_batch_size);
-			if rng.range(0..100) <= 25 {
-				spawner.max_batch_size += 1;
-			}
+			let size = rng.range(spawner.min_batch_size..=spawner.max_batch_size);
+			batch.0 += director.take(size);
 		}
 	}
 }
 
 fn spawners_spawning(
 	query: Query<(&Transform, &mut Spawner, &mut SpawnBatch)>,
+	prefabs: Res<EnemyPrefabs>,
+	director: Res<WaveDirector>,
 	time: Res<Time>,
 	mut commands: Commands,
 	mut rng: ResMut<RandomGen>,
@@ -209,15 +223,26 @@ fn spawners_spawning(
 
 		spawner.spawn_speed.tick(time.delta());
 		if spawner.spawn_speed.finished() {
+			let Some(idx) = rng.weighted_index(spawner.enemies.iter().map(|entry| entry.weight)) else {
+				continue;
+			};
+			//Missing prefabs keep their place in the wave until they load
+			let Some(prefab) = prefabs.0.get(&spawner.enemies[idx].archetype) else {
+				warn!("No enemy prefab for archetype {}", spawner.enemies[idx].archetype);
+				continue;
+			};
+			batch.0 -= 1;
 			let pos = transform.translation + rng.point_on_circle_vec3(spawner.spawn_range);
-			let idx = rng.range(0..spawner.prefabs.len());
-			commands
-				.entity(spawner.prefabs[idx])
+			let enemy = commands
+				.entity(*prefab)
 				.clone_and_spawn_with(|builder| {
 					builder.deny::<Disabled>();
 				})
-				.insert(Transform::from_translation(pos));
-			batch.0 -= 1;
+				.insert(Transform::from_translation(pos))
+				.id();
+			if let Some(elite) = roll_elite(&mut rng, &director) {
+				commands.entity(enemy).insert(elite);
+			}
 		}
 	}
 }
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+	components::{
+		ai::AI,
+		effects::Explosion,
+		stats::Life,
+		status::{InflictStatus, StatusApplication, StatusEffects},
+		tags::{Enemy, Owner, Projectile},
+	},
+	plugins::{death::death_events, utils::started_collision},
+	resources::utils::DamageEvent,
+	state_management::GameplaySystems,
+};
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+	fn build(&self, app: &mut App) {
+		//Projectiles are gone once FixedUpdate handles their hit, so the status is read off them first
+		app.add_systems(
+			FixedPreUpdate,
+			(apply_status_on_hit, tick_statuses, stun_ai)
+				.chain()
+				.in_set(GameplaySystems),
+		);
+		app.add_systems(
+			FixedUpdate,
+			spread_on_death.before(death_events).in_set(GameplaySystems),
+		);
+	}
+}
+
+//Timers tick without flagging the effects as changed, only an expiry triggers a new tint
+fn tick_statuses(
+	query: Query<(Entity, &mut StatusEffects, &Transform, &Life)>,
+	time: Res<Time>,
+	mut damage_events: EventWriter<DamageEvent>,
+) {
+	for (entity, mut statuses, transform, life) in query {
+		if life.is_dead() {
+			continue;
+		}
+		let mut expired = false;
+		for active in &mut statuses.bypass_change_detection().0 {
+			active.duration.tick(time.delta());
+			active.pulse.tick(time.delta());
+			let pulses = active.pulse.times_finished_this_tick() as f32;
+			let damage = active.kind.dps() * active.stacks as f32 * active.pulse.duration().as_secs_f32() * pulses;
+			if damage > 0. {
+				damage_events.write(DamageEvent {
+					source: entity,
+					target: entity,
+					amount: damage,
+					kind: active.kind.damage_kind(),
+					crit: default(),
+					origin: transform.translation.xy(),
+					knockback: 0.,
+					instigator: active.instigator,
+				});
+			}
+			expired |= active.duration.finished();
+		}
+		if expired {
+			statuses.0.retain(|active| !active.duration.finished());
+		}
+	}
+}
+
+fn stun_ai(query: Query<(&mut AI, &StatusEffects, &Life)>) {
+	for (mut ai, statuses, life) in query {
+		if life.is_dead() {
+			continue;
+		}
+		let stunned = statuses.is_stunned();
+		if ai.enabled == stunned {
+			ai.enabled = !stunned;
+		}
+	}
+}
+
+fn apply_status_on_hit(
+	inflictors: Query<(&InflictStatus, Option<&Projectile>, Option<&Explosion>)>,
+	mut targets: Query<(&mut StatusEffects, &Life)>,
+	mut collision_events: EventReader<CollisionEvent>,
+) {
+	for event in collision_events.read() {
+		if let Some((e, other)) = started_collision(event, |e| inflictors.contains(e))
+			&& let (Ok((inflict, projectile, explosion)), Ok((mut statuses, life))) =
+				(inflictors.get(e), targets.get_mut(other))
+			&& life.is_alive()
+		{
+			let owner = projectile
+				.map(|projectile| projectile.0)
+				.or(explosion.map(|explosion| explosion.owner))
+				.unwrap_or(Owner::Enemy);
+			statuses.apply(inflict.0, owner);
+		}
+	}
+}
+
+//Enemies that die while afflicted pass their effects on to the enemies around them
+fn spread_on_death(mut enemies: Query<(&mut StatusEffects, &Life, &Transform), With<Enemy>>) {
+	let mut spreads: Vec<(Vec2, StatusApplication, Owner)> = Vec::new();
+	for (statuses, life, transform) in &enemies {
+		//Life.1 is set once the death has been handled
+		if life.is_alive() || life.1 {
+			continue;
+		}
+		let pos = transform.translation.xy();
+		spreads.extend(
+			statuses
+				.0
+				.iter()
+				.filter(|active| active.kind.spread_range() > 0.)
+				.map(|active| (pos, active.application(), active.instigator)),
+		);
+	}
+	if spreads.is_empty() {
+		return;
+	}
+	for (mut statuses, life, transform) in &mut enemies {
+		if life.is_dead() {
+			continue;
+		}
+		let pos = transform.translation.xy();
+		for (origin, application, instigator) in &spreads {
+			let range = application.kind.spread_range();
+			if pos.distance_squared(*origin) <= range * range {
+				statuses.apply(*application, *instigator);
+			}
+		}
+	}
+}
@@ -3,15 +3,32 @@ use bevy::prelude::*;
 use crate::{
 	components::{
 		ai::*,
+		archetype::*,
+		boss::{Boss, BossAttack, BossPhase, BossState},
 		death::*,
-		effects::{Explosion, ExplosionProgress},
+		drops::{DropEntry, DropTable},
+		effects::{BaseColor, Explosion, ExplosionProgress, HitFlash},
+		elite::{Affix, Elite, EliteAura, Shield, Teleporter},
+		input::PlayerIntent,
 		spawner::*,
 		stats::*,
+		status::{InflictStatus, StatusEffects},
 		tags::*,
 		utils::*,
-		weapons::{ProjectileType, Weapon, WeaponAuto, WeaponBeam, WeaponBurst, WeaponFiring, WeaponSpread},
+		weapons::{
+			BeamVisual, Fragment, Fragmentation, Homing, ProjectileType, ScatterOnHit, Weapon, WeaponAuto, WeaponBeam,
+			WeaponBurst, WeaponFiring, WeaponInventory, WeaponSpread,
+		},
+	},
+	plugins::player::Dash,
+	resources::{
+		effects::{AfterimageMaterial, ExplosionMeshData, HitStop},
+		enemies::EnemyPrefabs,
+		input::{ActionMap, InputSource, LiveInput, StickSettings, TickInput},
+		upgrades::{LevelUpDraft, Rarity},
+		utils::{ArenaSize, DropPity, RunSeed},
+		waves::{EndlessDefinition, SpawnPoint, WaveDefinition},
 	},
-	resources::effects::ExplosionMeshData,
 };
 
 pub struct TypesPlugin;
@@ -20,36 +37,98 @@ impl Plugin for TypesPlugin {
 	fn build(&self, app: &mut App) {
 		app.register_type::<Health>();
 		app.register_type::<MaxHealth>();
+		app.register_type::<HealthRegen>();
 		app.register_type::<Damage>();
+		app.register_type::<Resistances>();
+		app.register_type::<Armor>();
+		app.register_type::<Critical>();
 		app.register_type::<Projectile>();
+		app.register_type::<KillOnContact>();
 		app.register_type::<FireRate>();
 		app.register_type::<Lifetime>();
 		app.register_type::<Life>();
 		app.register_type::<MoveSpeed>();
 		app.register_type::<MoveSpeedStat>();
 		app.register_type::<MoveSpeedMultiplier>();
+		app.register_type::<BaseStats>();
+		app.register_type::<Stats>();
+		app.register_type::<StatModifiers>();
 		app.register_type::<AI>();
 		app.register_type::<ChaseAI>();
 		app.register_type::<ChargeAI>();
 		app.register_type::<ChargeInfo>();
 		app.register_type::<HoverAI>();
+		app.register_type::<ShooterAI>();
 		app.register_type::<AITarget>();
 		app.register_type::<DeathExplosion>();
 		app.register_type::<DeathScatter>();
 		app.register_type::<ScatterPattern>();
+		app.register_type::<ScatterStage>();
+		app.register_type::<ScatterProgress>();
+		app.register_type::<Mine>();
+		app.register_type::<ChainArc>();
+		app.register_type::<ScatterOwner>();
+		app.register_type::<StatusEffects>();
+		app.register_type::<InflictStatus>();
+		app.register_type::<ChainLink>();
 		app.register_type::<Targeting>();
 		app.register_type::<Spawner>();
 		app.register_type::<SpawnBatch>();
+		app.register_type::<SpawnEntry>();
+		app.register_type::<WaveDefinition>();
+		app.register_type::<EndlessDefinition>();
+		app.register_type::<SpawnPoint>();
 		app.register_type::<Explosion>();
 		app.register_type::<ExplosionProgress>();
+		app.register_type::<BaseColor>();
+		app.register_type::<HitFlash>();
+		app.register_type::<IFrames>();
+		app.register_type::<Knockback>();
+		app.register_type::<Dash>();
+		app.register_type::<Experience>();
+		app.register_type::<ScatterOnHit>();
+		app.register_type::<Homing>();
+		app.register_type::<Fragmentation>();
+		app.register_type::<Fragment>();
+		app.register_type::<LevelUpDraft>();
+		app.register_type::<HitStop>();
+		app.register_type::<AfterimageMaterial>();
 		app.register_type::<ExplosionMeshData>();
+		app.register_type::<ArenaSize>();
+		app.register_type::<RunSeed>();
 		app.register_type::<Weapon>();
 		app.register_type::<WeaponFiring>();
+		app.register_type::<WeaponInventory>();
 		app.register_type::<WeaponAuto>();
 		app.register_type::<WeaponBeam>();
+		app.register_type::<BeamVisual>();
 		app.register_type::<WeaponBurst>();
 		app.register_type::<WeaponSpread>();
 		app.register_type::<ProjectileType>();
 		app.register_type::<Pickup>();
+		app.register_type::<DropTable>();
+		app.register_type::<DropEntry>();
+		app.register_type::<Rarity>();
+		app.register_type::<DropPity>();
+		app.register_type::<Enemy>();
+		app.register_type::<EnemyArchetype>();
+		app.register_type::<Boss>();
+		app.register_type::<BossPhase>();
+		app.register_type::<BossAttack>();
+		app.register_type::<BossState>();
+		app.register_type::<Elite>();
+		app.register_type::<Affix>();
+		app.register_type::<Shield>();
+		app.register_type::<Teleporter>();
+		app.register_type::<EliteAura>();
+		app.register_type::<EnemyBody>();
+		app.register_type::<BodyShape>();
+		app.register_type::<EnemyPrefabs>();
+		app.register_type::<PlayerIntent>();
+		app.register_type::<ActionMap>();
+		app.register_type::<InputSource>();
+		app.register_type::<LiveInput>();
+		app.register_type::<TickInput>();
+		app.register_type::<StickSettings>();
 	}
 }
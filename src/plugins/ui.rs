@@ -1,14 +1,22 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 
 use crate::{
 	components::{
-		stats::{Health, MaxHealth},
-		ui::{HealthBar, HealthBarText, HealthTextDisplayMode},
+		boss::Boss,
+		stats::{Experience, Health, Life, MaxHealth},
+		ui::{BossBar, DashBar, HealthBar, HealthBarText, HealthTextDisplayMode},
 		utils::Cleanable,
-		weapons::ProjectileType,
+		weapons::{ProjectileType, WeaponInventory},
+	},
+	plugins::{
+		input::PROJECTILE_SLOTS,
+		player::{Dash, Player},
+	},
+	resources::{
+		input::{Action, ActionMap},
+		utils::{ArenaSize, ChainStats, Fonts, KillCount},
+		waves::{WaveDirector, WaveState},
 	},
-	plugins::player::Player,
-	resources::utils::{Fonts, KillCount},
 	state_management::GameStartSystems,
 };
 
@@ -20,9 +28,16 @@ impl Plugin for UIPlugin {
 			Update,
 			(
 				update_health_bars,
+				update_dash_bars,
 				update_health_text,
 				update_kill_count_ui,
+				update_combo_ui,
+				update_wave_ui,
+				update_level_ui,
 				selected_projectile_display,
+				weapon_inventory_display,
+				spawn_boss_bars,
+				despawn_boss_bars,
 			),
 		);
 		app.add_systems(Update, spawn_ui.in_set(GameStartSystems));
@@ -32,8 +47,17 @@ impl Plugin for UIPlugin {
 #[derive(Component)]
 struct KillCountUI;
 
-fn spawn_ui(mut commands: Commands, window: Single<&Window, With<PrimaryWindow>>, fonts: Res<Fonts>) {
-	let size = window.size();
+#[derive(Component)]
+struct ComboUI;
+
+#[derive(Component)]
+struct WaveUI;
+
+#[derive(Component)]
+struct LevelUI;
+
+fn spawn_ui(mut commands: Commands, arena: Res<ArenaSize>, fonts: Res<Fonts>, actions: Res<ActionMap>) {
+	let size = arena.0;
 	commands.spawn((
 		Transform::from_xyz(0.0, (size.y / 2.0) - 20., 0.0),
 		Text2d::new("Kills: 0"),
@@ -46,7 +70,59 @@ fn spawn_ui(mut commands: Commands, window: Single<&Window, With<PrimaryWindow>>
 		KillCountUI,
 		Cleanable,
 	));
+	commands.spawn((
+		Transform::from_xyz(0.0, (size.y / 2.0) - 50., 0.0),
+		Text2d::new("Combo: x1.0"),
+		TextFont {
+			font: fonts.noto_thin.clone(),
+			font_size: 20.,
+			..default()
+		},
+		TextLayout::new_with_justify(JustifyText::Center),
+		ComboUI,
+		Cleanable,
+	));
+	commands.spawn((
+		Transform::from_xyz(0.0, (size.y / 2.0) - 80., 0.0),
+		Text2d::new("Wave 1"),
+		TextFont {
+			font: fonts.noto_thin.clone(),
+			font_size: 20.,
+			..default()
+		},
+		TextLayout::new_with_justify(JustifyText::Center),
+		WaveUI,
+		Cleanable,
+	));
+	commands.spawn((
+		Transform::from_xyz(0.0, (size.y / 2.0) - 110., 0.0),
+		Text2d::new("Level 1"),
+		TextFont {
+			font: fonts.noto_thin.clone(),
+			font_size: 20.,
+			..default()
+		},
+		TextLayout::new_with_justify(JustifyText::Center),
+		LevelUI,
+		Cleanable,
+	));
+	commands.spawn((
+		Transform::from_xyz(0., (size.y / -2.) + 130., 10.),
+		WeaponDisplay,
+		Text2d::new("Weapons"),
+		TextFont {
+			font: fonts.noto.clone(),
+			font_size: 18.,
+			..default()
+		},
+		TextLayout::new_with_justify(JustifyText::Center),
+		Cleanable,
+	));
 	let pos = Vec3::new(0., (size.y / -2.) + 80., 10.);
+	let projectile_hint = (1..=PROJECTILE_SLOTS)
+		.map(|slot| format!("[{}]", actions.label(Action::SelectProjectile(slot))))
+		.collect::<Vec<_>>()
+		.join(" ");
 	commands.spawn((
 		Transform::from_translation(pos),
 		ProjectileDisplay,
@@ -60,7 +136,7 @@ fn spawn_ui(mut commands: Commands, window: Single<&Window, With<PrimaryWindow>>
 		Cleanable,
 		children![(
 			Transform::from_xyz(0.0, 20., 0.0),
-			Text2d::new("[1] [2] [3] [4]"),
+			Text2d::new(projectile_hint),
 			TextFont {
 				font: fonts.noto.clone(),
 				font_size: 15.,
@@ -71,10 +147,109 @@ fn spawn_ui(mut commands: Commands, window: Single<&Window, With<PrimaryWindow>>
 	));
 }
 
+fn spawn_boss_bars(
+	bosses: Query<(Entity, &Boss), Added<Boss>>,
+	arena: Res<ArenaSize>,
+	fonts: Res<Fonts>,
+	mut commands: Commands,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+	const BAR_SIZE: Vec2 = Vec2::new(500., 16.);
+	let pos = Vec3::new(0., (arena.0.y / 2.) - 150., 10.);
+	for (entity, boss) in bosses {
+		commands.spawn((
+			Name::new("Boss Name"),
+			Transform::from_translation(pos + Vec3::Y * BAR_SIZE.y * 1.2),
+			BossBar(entity),
+			Text2d::new(boss.name.clone()),
+			TextFont {
+				font: fonts.noto.clone(),
+				font_size: 18.,
+				..default()
+			},
+			TextLayout::new_with_justify(JustifyText::Center),
+			Cleanable,
+		));
+		commands.spawn((
+			Name::new("Boss Health Bar"),
+			Transform::from_translation(pos),
+			BossBar(entity),
+			HealthBar(entity),
+			Mesh2d(meshes.add(Rectangle::from_size(BAR_SIZE))),
+			MeshMaterial2d(materials.add(Color::linear_rgb(0.8, 0.1, 1.0))),
+			Cleanable,
+		));
+		commands.spawn((
+			Name::new("Boss Health Bar Text"),
+			Transform::from_translation(pos + Vec3::Z),
+			BossBar(entity),
+			HealthBarText {
+				display: HealthTextDisplayMode::Percentage,
+				show_max: false,
+				health_entity: entity,
+			},
+			TextFont {
+				font: fonts.noto_regular.clone(),
+				font_size: BAR_SIZE.y * 0.8,
+				..default()
+			},
+			TextColor(LinearRgba::BLACK.into()),
+			TextLayout::new_with_justify(JustifyText::Center),
+			Cleanable,
+		));
+		commands.spawn((
+			Name::new("Boss Health Bar Fill"),
+			Transform::from_translation(pos - Vec3::Z),
+			BossBar(entity),
+			Mesh2d(meshes.add(Rectangle::from_size(BAR_SIZE))),
+			MeshMaterial2d(materials.add(Color::linear_rgb(0.2, 0.0, 0.2))),
+			Cleanable,
+		));
+	}
+}
+
+fn despawn_boss_bars(bars: Query<(Entity, &BossBar)>, bosses: Query<&Life, With<Boss>>, mut commands: Commands) {
+	for (entity, bar) in bars {
+		if bosses.get(bar.0).is_ok_and(Life::is_alive) {
+			continue;
+		}
+		commands.entity(entity).despawn();
+	}
+}
+
+fn update_level_ui(mut text: Single<&mut Text2d, With<LevelUI>>, player: Single<&Experience, With<Player>>) {
+	text.0 = format!("Level {} ({}/{} XP)", player.level, player.xp, player.required());
+}
+
 fn update_kill_count_ui(mut text: Single<&mut Text2d, With<KillCountUI>>, count: Res<KillCount>) {
 	text.0 = format!("KIlls: {}", count.0);
 }
 
+fn update_combo_ui(mut text: Single<&mut Text2d, With<ComboUI>>, chains: Res<ChainStats>) {
+	text.0 = match chains.current() {
+		Some(chain) if chain.length > 1 => format!(
+			"Combo: x{:.1} (Chain {}, Depth {})",
+			chain.multiplier(),
+			chain.length,
+			chain.depth
+		),
+		_ => "Combo: x1.0".to_string(),
+	};
+}
+
+fn update_wave_ui(mut text: Single<&mut Text2d, With<WaveUI>>, director: Res<WaveDirector>) {
+	let wave = match director.state {
+		WaveState::Delay(ref timer) => format!("Wave {} in {}", director.wave + 1, timer.remaining_secs().ceil()),
+		_ => format!("Wave {}", director.wave.max(1)),
+	};
+	text.0 = if director.endless {
+		format!("{} (Endless)", wave)
+	} else {
+		wave
+	};
+}
+
 fn update_health_bars(health_bars: Query<(&mut Transform, &HealthBar)>, healths: Query<(&Health, &MaxHealth)>) {
 	for (mut transform, bar) in health_bars {
 		if let Ok((health, max_health)) = healths.get(bar.0) {
@@ -84,6 +259,14 @@ fn update_health_bars(health_bars: Query<(&mut Transform, &HealthBar)>, healths:
 	}
 }
 
+fn update_dash_bars(dash_bars: Query<(&mut Transform, &DashBar)>, dashes: Query<&Dash>) {
+	for (mut transform, bar) in dash_bars {
+		if let Ok(dash) = dashes.get(bar.0) {
+			transform.scale = Vec3::new(dash.cooldown.fraction(), 1.0, 1.0);
+		}
+	}
+}
+
 fn update_health_text(texts: Query<(&mut Text2d, &HealthBarText)>, healths: Query<(&Health, &MaxHealth)>) {
 	for (mut text, health_text) in texts {
 		if let Ok((health, max)) = healths.get(health_text.health_entity) {
@@ -111,6 +294,27 @@ fn update_health_text(texts: Query<(&mut Text2d, &HealthBarText)>, healths: Quer
 #[derive(Component)]
 struct ProjectileDisplay;
 
+#[derive(Component)]
+struct WeaponDisplay;
+
+//Every held weapon with its level, the one in use marked
+fn weapon_inventory_display(
+	player: Single<&WeaponInventory, With<Player>>,
+	mut display: Single<&mut Text2d, With<WeaponDisplay>>,
+	actions: Res<ActionMap>,
+) {
+	let inventory = player.into_inner();
+	let slots = (0..inventory.slots).map(|i| {
+		let key = actions.label(Action::SelectWeapon(i as u8 + 1));
+		match inventory.held.get(i) {
+			Some(held) if i == inventory.current => format!("> [{key}] {} Lv{} <", held.weapon.label(), held.level),
+			Some(held) => format!("[{key}] {} Lv{}", held.weapon.label(), held.level),
+			None => format!("[{key}] -"),
+		}
+	});
+	display.0 = slots.collect::<Vec<_>>().join("   ");
+}
+
 fn selected_projectile_display(
 	player: Single<&ProjectileType, With<Player>>,
 	mut display: Single<&mut Text2d, With<ProjectileDisplay>>,
@@ -121,6 +325,7 @@ fn selected_projectile_display(
 		ProjectileType::Piercing { .. } => "[2] Piercing",
 		ProjectileType::Bouncing { .. } => "[3] Bouncing",
 		ProjectileType::Grenade { .. } => "[4] Grenade",
+		ProjectileType::Homing { .. } => "[5] Homing",
 	};
 	display.0 = format!("Projectile: {}", name);
 }
@@ -1,8 +1,13 @@
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::CollisionEvent;
 
 use crate::{
 	components::{
-		stats::{Life, MoveSpeed, MoveSpeedMultiplier, MoveSpeedStat},
+		stats::{
+			BaseStats, HealthRegen, Life, MaxHealth, MoveSpeed, MoveSpeedMultiplier, MoveSpeedStat, StatModifiers,
+			Stats,
+		},
+		status::StatusEffects,
 		utils::{Cleanable, Lifetime},
 	},
 	state_management::{GameOverSystems, GameplaySystems},
@@ -13,15 +18,20 @@ pub struct UtilsPlugin;
 impl Plugin for UtilsPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_systems(
-			PostUpdate,
+			FixedPostUpdate,
 			(process_lifetimes, process_lifetimes_life).in_set(GameOverSystems),
 		);
 		app.add_systems(
-			PostUpdate,
+			FixedPostUpdate,
 			(process_lifetimes, process_lifetimes_life).in_set(GameplaySystems),
 		);
-		app.add_systems(PreUpdate, process_move_speed.in_set(GameplaySystems));
-		app.add_systems(PreUpdate, process_move_speed.in_set(GameOverSystems));
+		app.add_systems(
+			FixedPreUpdate,
+			(tick_modifiers, recompute_stats, process_move_speed)
+				.chain()
+				.in_set(GameplaySystems),
+		);
+		app.add_systems(FixedPreUpdate, process_move_speed.in_set(GameOverSystems));
 	}
 }
 fn process_lifetimes(
@@ -51,9 +61,60 @@ fn process_lifetimes_life(mut query: Query<(&mut Lifetime, &mut Life)>, time: Re
 	}
 }
 
-fn process_move_speed(mut query: Query<(&mut MoveSpeed, &MoveSpeedStat, &MoveSpeedMultiplier)>) {
-	for (mut adj, speed, multi) in &mut query {
-		adj.0 = speed.0 * multi.0;
+//Timers tick without flagging the stack as changed, only an expiry triggers a recompute
+fn tick_modifiers(query: Query<&mut StatModifiers>, time: Res<Time>) {
+	for mut modifiers in query {
+		let mut expired = false;
+		for modifier in &mut modifiers.bypass_change_detection().0 {
+			if let Some(duration) = &mut modifier.duration {
+				duration.tick(time.delta());
+				expired |= duration.finished();
+			}
+		}
+		if expired {
+			modifiers.0.retain(|modifier| !modifier.is_expired());
+		}
+	}
+}
+
+fn recompute_stats(
+	query: Query<
+		(
+			&BaseStats,
+			&StatModifiers,
+			&mut Stats,
+			Option<&mut MaxHealth>,
+			Option<&mut HealthRegen>,
+			Option<&mut MoveSpeedStat>,
+		),
+		Or<(Changed<BaseStats>, Changed<StatModifiers>)>,
+	>,
+) {
+	for (base, modifiers, mut stats, max_health, regen, move_speed) in query {
+		stats.0 = modifiers.resolve_all(&base.0);
+		if let Some(mut max_health) = max_health {
+			max_health.0 = stats.0.max_health;
+		}
+		if let Some(mut regen) = regen {
+			regen.0 = stats.0.regen;
+		}
+		if let Some(mut move_speed) = move_speed {
+			move_speed.0 = stats.0.move_speed;
+		}
+	}
+}
+
+//Status effects slow on top of whatever the entity's own behaviour sets the multiplier to
+fn process_move_speed(
+	mut query: Query<(
+		&mut MoveSpeed,
+		&MoveSpeedStat,
+		&MoveSpeedMultiplier,
+		Option<&StatusEffects>,
+	)>,
+) {
+	for (mut adj, speed, multi, statuses) in &mut query {
+		adj.0 = speed.0 * multi.0 * statuses.map_or(1., StatusEffects::speed_multiplier);
 	}
 }
 
@@ -70,3 +131,15 @@ pub fn play_audio_onshot_spacial(commands: &mut Commands, pos: Vec3, audio: Hand
 		Cleanable,
 	));
 }
+
+//Matches a started collision against either entity, returning (matched, other)
+pub fn started_collision(event: &CollisionEvent, matches: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+	if let CollisionEvent::Started(a, b, _) = event {
+		if matches(*a) {
+			return Some((*a, *b));
+		} else if matches(*b) {
+			return Some((*b, *a));
+		}
+	}
+	return None;
+}
@@ -0,0 +1,177 @@
+use bevy::{ecs::entity_disabling::Disabled, prelude::*};
+
+use crate::{
+	components::{
+		spawner::{SpawnBatch, Spawner},
+		stats::{Health, Life, MaxHealth},
+		tags::Enemy,
+		utils::Cleanable,
+	},
+	plugins::player::Player,
+	resources::{
+		enemies::EnemyPrefabs,
+		utils::DeathEvent,
+		waves::{WaveDefinition, WaveDirector, WaveScript, WaveScriptHandle, WaveScriptLoader, WaveState},
+	},
+	state_management::{GameStartSystems, GameplaySystems},
+};
+
+const WAVE_SCRIPT_PATH: &str = "waves/default.waves.ron";
+const SPAWN_RANGE: f32 = 100.;
+const SPAWN_SPEED: f32 = 0.5;
+//How quickly the kill rate average and intensity react
+const KILL_RATE_SMOOTHING: f32 = 0.2;
+const INTENSITY_ADAPT_RATE: f32 = 0.02;
+const MIN_INTENSITY: f32 = 0.5;
+const MAX_INTENSITY: f32 = 3.;
+const BOSS_SPAWN: Vec3 = Vec3::new(0., 250., 0.);
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_asset::<WaveScript>();
+		app.init_asset_loader::<WaveScriptLoader>();
+		app.init_resource::<WaveDirector>();
+		app.register_type::<WaveDirector>();
+		app.add_systems(Startup, load_script);
+		app.add_systems(Update, reset_director.in_set(GameStartSystems));
+		app.add_systems(
+			FixedUpdate,
+			(track_intensity, run_director).chain().in_set(GameplaySystems),
+		);
+	}
+}
+
+fn load_script(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(WaveScriptHandle(asset_server.load(WAVE_SCRIPT_PATH)));
+}
+
+fn reset_director(mut director: ResMut<WaveDirector>) {
+	*director = WaveDirector::default();
+}
+
+fn run_director(
+	mut director: ResMut<WaveDirector>,
+	script: Res<WaveScriptHandle>,
+	scripts: Res<Assets<WaveScript>>,
+	spawners: Query<(Entity, &SpawnBatch), With<Spawner>>,
+	enemies: Query<&Life, With<Enemy>>,
+	prefabs: Res<EnemyPrefabs>,
+	time: Res<Time>,
+	mut commands: Commands,
+) {
+	let Some(script) = scripts.get(&script.0) else {
+		return;
+	};
+	match director.state {
+		WaveState::Idle => {
+			let delay = next_wave(&director, script).delay;
+			director.state = WaveState::Delay(Timer::from_seconds(delay, TimerMode::Once));
+		}
+		WaveState::Delay(ref mut timer) => {
+			timer.tick(time.delta());
+			if timer.finished() {
+				let wave = next_wave(&director, script);
+				director.wave += 1;
+				director.endless = director.wave as usize > script.waves.len();
+				director.remaining = wave.count;
+				director.state = WaveState::Spawning;
+				for (entity, _) in spawners {
+					commands.entity(entity).despawn();
+				}
+				spawn_wave(&mut commands, &wave);
+				if let Some(boss) = &wave.boss {
+					spawn_boss(&mut commands, &prefabs, boss);
+				}
+				info!("Starting wave {}", director.wave);
+			}
+		}
+		WaveState::Spawning => {
+			if director.remaining == 0 {
+				director.state = WaveState::Clearing;
+			}
+		}
+		WaveState::Clearing => {
+			let spawning = spawners.iter().any(|(_, batch)| batch.0 > 0);
+			let alive = enemies.iter().any(|life| life.is_alive());
+			if !spawning && !alive {
+				director.state = WaveState::Idle;
+			}
+		}
+	}
+}
+
+fn next_wave(director: &WaveDirector, script: &WaveScript) -> WaveDefinition {
+	match script.waves.get(director.wave as usize) {
+		Some(wave) => wave.clone(),
+		None => script
+			.endless
+			.generate(director.wave - script.waves.len() as u32, director.intensity),
+	}
+}
+
+fn spawn_boss(commands: &mut Commands, prefabs: &EnemyPrefabs, archetype: &str) {
+	let Some(prefab) = prefabs.0.get(archetype) else {
+		warn!("No enemy prefab for boss {}", archetype);
+		return;
+	};
+	commands
+		.entity(*prefab)
+		.clone_and_spawn_with(|builder| {
+			builder.deny::<Disabled>();
+		})
+		.insert(Transform::from_translation(BOSS_SPAWN));
+	info!("Boss {} appeared", archetype);
+}
+
+fn spawn_wave(commands: &mut Commands, wave: &WaveDefinition) {
+	for point in &wave.spawners {
+		let pos = (Vec2::from_angle(point.angle.to_radians()) * point.distance).extend(0.);
+		let mut spawn_rate = Timer::from_seconds(wave.batch_interval, TimerMode::Repeating);
+		//Kick off the first batch straight away
+		spawn_rate.set_elapsed(spawn_rate.duration());
+		commands.spawn((
+			Name::new("Spawner"),
+			Transform::from_translation(pos),
+			Cleanable,
+			Spawner {
+				max_batch_size: wave.max_batch,
+				min_batch_size: wave.min_batch,
+				enemies: wave.enemies.clone(),
+				spawn_effect: Entity::PLACEHOLDER,
+				spawn_range: SPAWN_RANGE,
+				spawn_rate,
+				spawn_speed: Timer::from_seconds(SPAWN_SPEED, TimerMode::Repeating),
+			},
+		));
+	}
+}
+
+//Endless waves get harder while the player is healthy and keeping up with kills, and ease off otherwise
+fn track_intensity(
+	mut director: ResMut<WaveDirector>,
+	mut deaths: EventReader<DeathEvent>,
+	script: Res<WaveScriptHandle>,
+	scripts: Res<Assets<WaveScript>>,
+	player: Single<(&Health, &MaxHealth), With<Player>>,
+	time: Res<Time>,
+) {
+	let delta = time.delta_secs();
+	if delta <= 0. {
+		return;
+	}
+	let kills = deaths.read().filter(|death| !death.is_player).count() as f32;
+	let smoothing = (KILL_RATE_SMOOTHING * delta).min(1.);
+	director.kill_rate += (kills / delta - director.kill_rate) * smoothing;
+
+	let Some(script) = scripts.get(&script.0) else {
+		return;
+	};
+	let (health, max_health) = player.into_inner();
+	let health = (health.0 / max_health.0).clamp(0., 1.);
+	let pace = (director.kill_rate / script.endless.target_kill_rate.max(f32::EPSILON)).min(2.);
+	let pressure = (health + pace) * 0.5 - 0.5;
+	director.intensity =
+		(director.intensity + pressure * INTENSITY_ADAPT_RATE * delta).clamp(MIN_INTENSITY, MAX_INTENSITY);
+}
@@ -1,30 +1,72 @@
-use bevy::prelude::*;
+use bevy::{ecs::bundle::NoBundleEffect, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-	ENEMY_OWNED_GROUP, PLAYER_OWNED_GROUP, PLAYER_PROJECTILE_GROUP,
+	ENEMY_OWNED_GROUP, ENEMY_PROJECTILE_GROUP, PLAYER_OWNED_GROUP, PLAYER_PROJECTILE_GROUP,
 	components::{
-		death::{DeathScatter, ScatterPattern},
-		stats::{Damage, Life},
+		death::{DeathScatter, ScatterPattern, ScatterStage},
+		stats::{Critical, Damage, Knockback, Life, StatBlock, Stats},
+		status::{InflictStatus, StatusApplication},
 		tags::{ContactLimit, Owner, Projectile},
 		utils::Lifetime,
 		weapons::*,
 	},
 	plugins::{player::Player, utils::play_audio_onshot},
-	resources::{audio::AudioClips, utils::RandomGen},
-	state_management::GameplaySystems,
+	resources::{
+		audio::AudioClips,
+		utils::{DamageEvent, DamageKind, RandomGen},
+	},
+	state_management::{GameOverSystems, GameplaySystems, RngSystems},
 };
 
 pub struct WeaponsPlugin;
 
 impl Plugin for WeaponsPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(Update, weapon_firing.in_set(GameplaySystems));
+		app.register_type::<Beams>();
+		app.add_systems(Startup, init_meshes);
+		app.add_systems(
+			FixedUpdate,
+			weapon_firing.in_set(GameplaySystems).in_set(RngSystems::Weapons),
+		);
+		app.add_systems(Update, update_beams.in_set(GameplaySystems));
+		app.add_systems(PostUpdate, init_beams.in_set(GameplaySystems));
+		app.add_systems(Update, update_beams.in_set(GameOverSystems));
+	}
+}
+
+#[derive(Resource, Reflect, Default)]
+struct Beams {
+	mesh: Handle<Mesh>,
+	mat: Handle<ColorMaterial>,
+}
+
+const BEAM_WIDTH: f32 = 3.;
+const MUZZLE_OFFSET: f32 = 10.;
+
+fn init_meshes(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>) {
+	commands.insert_resource(Beams {
+		mesh: meshes.add(Rectangle::new(BEAM_WIDTH, 1.)),
+		mat: materials.add(Color::linear_rgb(0.6, 0.0, 3.0)),
+	});
+}
+
+fn init_beams(query: Query<Entity, Added<WeaponBeam>>, beams: Res<Beams>, mut commands: Commands) {
+	for entity in query {
+		commands.entity(entity).with_child((
+			Name::new("Beam"),
+			BeamVisual,
+			Transform::from_translation(Vec3::NEG_Z),
+			Visibility::Hidden,
+			Mesh2d(beams.mesh.clone()),
+			MeshMaterial2d(beams.mat.clone()),
+		));
 	}
 }
 
 fn weapon_firing(
 	query: Query<(
+		Entity,
 		&Transform,
 		&mut Velocity,
 		&WeaponFiring,
@@ -35,36 +77,60 @@ fn weapon_firing(
 		&mut WeaponBurst,
 		&mut WeaponSpread,
 		&ProjectileType,
+		Option<&Stats>,
 		Option<&Player>,
 	)>,
 	time: Res<Time>,
 	mut commands: Commands,
 	mut rng: ResMut<RandomGen>,
 	audio: Res<AudioClips>,
+	rapier_context: ReadRapierContext,
+	mut damage_events: EventWriter<DamageEvent>,
 ) {
-	for (transform, mut vel, firing, life, weapon, mut _beam, mut auto, mut burst, mut spread, proj, player) in query {
+	let Ok(rapier) = rapier_context.single() else {
+		return;
+	};
+	for (
+		entity,
+		transform,
+		mut vel,
+		firing,
+		life,
+		weapon,
+		mut beam,
+		mut auto,
+		mut burst,
+		mut spread,
+		proj,
+		stats,
+		player,
+	) in query
+	{
 		if life.is_dead() {
 			continue;
 		}
+		let stats = stats.map(|stats| stats.0).unwrap_or_default();
+		let delta = time.delta().mul_f32(stats.fire_rate);
+		let crit = Critical::from(&stats);
 		let owner = if player.is_some() { Owner::Player } else { Owner::Enemy };
 		let aim = transform.up().as_vec3();
 		match weapon {
 			Weapon::Auto => {
 				if !auto.fire_rate.finished() {
-					auto.fire_rate.tick(time.delta());
+					auto.fire_rate.tick(delta);
 				}
 				if firing.0 && auto.fire_rate.finished() {
 					vel.linvel += transform.up().xy() * -auto.recoil;
 					play_audio_onshot(&mut commands, audio.shoot_auto.clone());
 					let volley = proj.multishot() * auto.fire_rate.times_finished_this_tick();
-					prepare_auto_volley(volley, aim, transform.translation, &auto, proj, owner, &mut rng)
-						.spawn(&mut commands);
-					auto.fire_rate.tick(time.delta());
+					prepare_auto_volley(volley, aim, transform.translation, &auto, proj, &stats, owner, &mut rng)
+						.spawn(&mut commands, crit, proj.status());
+					auto.fire_rate.tick(delta);
 				}
 			}
 			Weapon::Spread => {
 				if !spread.fire_rate.finished() {
-					spread.fire_rate.tick(time.delta());
+					spread.fire_rate.tick(delta);
 				}
 				if firing.0 && spread.fire_rate.finished() {
 					vel.linvel += transform.up().xy() * -spread.recoil;
@@ -72,9 +138,17 @@ fn weapon_firing(
 					let angle_offset = rng.range((-spread.accuracy)..spread.accuracy);
 					let adjusted_aim = Quat::from_axis_angle(Vec3::Z, angle_offset.to_radians()) * aim;
 					let volley = (proj.multishot() + spread.shot_count) * spread.fire_rate.times_finished_this_tick();
-					prepare_spread_volley(volley, adjusted_aim, transform.translation, &spread, proj, owner)
-						.spawn(&mut commands);
-					spread.fire_rate.tick(time.delta());
+					prepare_spread_volley(
+						volley,
+						adjusted_aim,
+						transform.translation,
+						&spread,
+						proj,
+						&stats,
+						owner,
+					)
+					.spawn(&mut commands, crit, proj.status());
+					spread.fire_rate.tick(delta);
 				}
 			}
 			Weapon::Burst => {
@@ -84,25 +158,113 @@ fn weapon_firing(
 				}
 				if burst.cur_burst == 0 {
 					if !burst.fire_rate.finished() {
-						burst.fire_rate.tick(time.delta());
+						burst.fire_rate.tick(delta);
 					}
 					if firing.0 && burst.fire_rate.finished() {
 						burst.cur_burst = (proj.multishot() + burst.burst) * burst.fire_rate.times_finished_this_tick();
-						burst.fire_rate.tick(time.delta());
+						burst.fire_rate.tick(delta);
 					}
 				} else {
-					burst.burst_rate.tick(time.delta());
+					burst.burst_rate.tick(delta);
 					if firing.0 && burst.burst_rate.finished() {
 						vel.linvel += transform.up().xy() * -burst.recoil;
 						play_audio_onshot(&mut commands, audio.shoot_burst.clone());
 						let shots = burst.burst_rate.times_finished_this_tick().min(burst.cur_burst);
 						burst.cur_burst -= shots;
-						prepare_burst_volley(shots, aim, transform.translation, &burst, proj, owner, &mut rng)
-							.spawn(&mut commands);
+						prepare_burst_volley(shots, aim, transform.translation, &burst, proj, &stats, owner, &mut rng)
+							.spawn(&mut commands, crit, proj.status());
+					}
+				}
+			}
+			Weapon::Beam => {
+				if !beam.hit_rate.finished() {
+					beam.hit_rate.tick(delta);
+				}
+				if firing.0 {
+					vel.linvel += transform.up().xy() * -beam.recoil * time.delta_secs();
+					let origin = transform.translation.xy() + aim.xy() * MUZZLE_OFFSET;
+					let filter = QueryFilter::new()
+						.exclude_sensors()
+						.exclude_rigid_body(entity)
+						.groups(beam_groups(owner));
+					let hit = rapier.cast_ray(origin, aim.xy(), beam.max_range, true, filter);
+					beam.length = hit.map(|(_, dist)| dist).unwrap_or(beam.max_range);
+					if beam.hit_rate.finished() {
+						if let Some((target, _)) = hit {
+							let ticks = beam.hit_rate.times_finished_this_tick() as f32;
+							damage_events.write(DamageEvent {
+								source: entity,
+								target,
+								amount: proj.damage() * beam.damage_multi * stats.damage * ticks,
+								kind: DamageKind::Energy,
+								crit,
+								origin,
+								knockback: BEAM_KNOCKBACK,
+								instigator: owner,
+							});
+						}
+						beam.hit_rate.tick(delta);
 					}
 				}
 			}
-			Weapon::Beam => todo!(),
+		}
+	}
+}
+
+//Beams pass through projectiles and effects, only stopping at walls and whatever they can damage
+fn beam_groups(owner: Owner) -> CollisionGroups {
+	match owner {
+		Owner::Player => CollisionGroups::new(
+			PLAYER_PROJECTILE_GROUP,
+			Group::ALL ^ PLAYER_OWNED_GROUP ^ ENEMY_PROJECTILE_GROUP,
+		),
+		Owner::Enemy => CollisionGroups::new(
+			ENEMY_PROJECTILE_GROUP,
+			Group::ALL ^ ENEMY_OWNED_GROUP ^ PLAYER_PROJECTILE_GROUP,
+		),
+	}
+}
+
+struct ProjGroups {
+	member: Group,
+	//Everything except the owner's side, so shots also clash with opposing projectiles
+	wide: Group,
+	//Only walls and the opposing side
+	targets: Group,
+}
+
+impl ProjGroups {
+	fn of(owner: Owner) -> Self {
+		match owner {
+			Owner::Player => Self {
+				member: PLAYER_PROJECTILE_GROUP,
+				wide: Group::ALL ^ PLAYER_OWNED_GROUP,
+				targets: ENEMY_OWNED_GROUP,
+			},
+			Owner::Enemy => Self {
+				member: ENEMY_PROJECTILE_GROUP,
+				wide: Group::ALL ^ ENEMY_OWNED_GROUP,
+				targets: PLAYER_OWNED_GROUP,
+			},
+		}
+	}
+}
+
+fn update_beams(
+	beams: Query<(&mut Transform, &mut Visibility, &ChildOf), With<BeamVisual>>,
+	weapons: Query<(&Weapon, &WeaponBeam, &WeaponFiring, &Life)>,
+) {
+	for (mut transform, mut visibility, parent) in beams {
+		if let Ok((weapon, beam, firing, life)) = weapons.get(parent.0) {
+			if let Weapon::Beam = weapon
+				&& firing.0 && life.is_alive()
+			{
+				transform.translation.y = MUZZLE_OFFSET + beam.length / 2.;
+				transform.scale.y = beam.length;
+				*visibility = Visibility::Inherited;
+				continue;
+			}
+			*visibility = Visibility::Hidden;
 		}
 	}
 }
@@ -120,19 +282,37 @@ struct ProjBundle {
 	contacts: ContactLimit,
 	life: Lifetime,
 	drag: Damping,
+	knockback: Knockback,
+	fragmentation: Fragmentation,
 }
 
 impl ProjBatch {
-	pub fn spawn(self, commands: &mut Commands) {
+	pub fn spawn(self, commands: &mut Commands, crit: Critical, status: Option<StatusApplication>) {
 		match self {
-			ProjBatch::Normal(proj_bundles) => commands.spawn_batch(proj_bundles),
-			ProjBatch::Bounce(bounce_projs) => commands.spawn_batch(bounce_projs),
-			ProjBatch::Sensor(sensor_projs) => commands.spawn_batch(sensor_projs),
-			ProjBatch::Scatter(scatter_projs) => commands.spawn_batch(scatter_projs),
+			ProjBatch::Normal(proj_bundles) => spawn_with(commands, proj_bundles, crit, status),
+			ProjBatch::Bounce(bounce_projs) => spawn_with(commands, bounce_projs, crit, status),
+			ProjBatch::Sensor(sensor_projs) => spawn_with(commands, sensor_projs, crit, status),
+			ProjBatch::Homing(homing_projs) => spawn_with(commands, homing_projs, crit, status),
+			//Grenades pass their crit and status on to the explosion
+			ProjBatch::Scatter(scatter_projs) => {
+				commands.spawn_batch(scatter_projs.into_iter().map(move |proj| (proj, crit)))
+			}
 		}
 	}
 }
 
+fn spawn_with<B: Bundle<Effect: NoBundleEffect>>(
+	commands: &mut Commands,
+	bundles: Vec<B>,
+	crit: Critical,
+	status: Option<StatusApplication>,
+) {
+	match status {
+		Some(status) => commands.spawn_batch(bundles.into_iter().map(move |proj| (proj, crit, InflictStatus(status)))),
+		None => commands.spawn_batch(bundles.into_iter().map(move |proj| (proj, crit))),
+	}
+}
+
 #[derive(Bundle, Default)]
 struct SensorProj(ProjBundle, Sensor);
 
@@ -142,11 +322,15 @@ struct ScatterProj(ProjBundle, DeathScatter);
 #[derive(Bundle)]
 struct BounceProj(ProjBundle, Restitution);
 
+#[derive(Bundle)]
+struct HomingProj(ProjBundle, Homing);
+
 enum ProjBatch {
 	Normal(Vec<ProjBundle>),
 	Bounce(Vec<BounceProj>),
 	Sensor(Vec<SensorProj>),
 	Scatter(Vec<ScatterProj>),
+	Homing(Vec<HomingProj>),
 }
 
 impl Default for ProjBatch {
@@ -155,6 +339,10 @@ impl Default for ProjBatch {
 	}
 }
 const PROJECTILE_SIZE: f32 = 2.;
+const PROJECTILE_KNOCKBACK: f32 = 30.;
+//Applied per hit tick rather than per projectile
+const BEAM_KNOCKBACK: f32 = 15.;
+const EXPLOSION_KNOCKBACK: f32 = 200.;
 const PROJECTILE_LIFETIME: f32 = 5.;
 const DEFAULT_MAX_CONTACT: u32 = 1;
 const DEFAULT_DRAG: f32 = 0.0;
@@ -165,10 +353,17 @@ fn prepare_spread_volley(
 	pos: Vec3,
 	spread: &WeaponSpread,
 	proj: &ProjectileType,
+	stats: &StatBlock,
 	owner: Owner,
 ) -> ProjBatch {
 	let aim_pos = determine_spread_aim_and_pos(pos, aim, 10., spread.arc, volley);
-	return create_projectile_batch(proj, owner, spread.speed_multi, spread.damage_multi, aim_pos);
+	return create_projectile_batch(
+		proj,
+		owner,
+		spread.speed_multi * stats.projectile_speed,
+		spread.damage_multi * stats.damage,
+		aim_pos,
+	);
 }
 
 fn prepare_auto_volley(
@@ -177,11 +372,18 @@ fn prepare_auto_volley(
 	pos: Vec3,
 	auto: &WeaponAuto,
 	proj: &ProjectileType,
+	stats: &StatBlock,
 	owner: Owner,
 	rng: &mut RandomGen,
 ) -> ProjBatch {
 	let aim_pos = determine_aim_and_pos(pos, aim, 10., auto.accuracy / 2., volley, rng);
-	return create_projectile_batch(proj, owner, auto.speed_multi, auto.damage_multi, aim_pos);
+	return create_projectile_batch(
+		proj,
+		owner,
+		auto.speed_multi * stats.projectile_speed,
+		auto.damage_multi * stats.damage,
+		aim_pos,
+	);
 }
 
 fn prepare_burst_volley(
@@ -190,11 +392,18 @@ fn prepare_burst_volley(
 	pos: Vec3,
 	burst: &WeaponBurst,
 	proj: &ProjectileType,
+	stats: &StatBlock,
 	owner: Owner,
 	rng: &mut RandomGen,
 ) -> ProjBatch {
 	let aim_pos = determine_aim_and_pos(pos, aim, 10., burst.accuracy / 2., volley, rng);
-	return create_projectile_batch(proj, owner, burst.speed_multi, burst.damage_multi, aim_pos);
+	return create_projectile_batch(
+		proj,
+		owner,
+		burst.speed_multi * stats.projectile_speed,
+		burst.damage_multi * stats.damage,
+		aim_pos,
+	);
 }
 
 fn create_projectile_batch(
@@ -204,6 +413,8 @@ fn create_projectile_batch(
 	damage_multi: f32,
 	aim_pos: Vec<(Vec2, Vec3)>,
 ) -> ProjBatch {
+	let groups = ProjGroups::of(owner);
+	let fragmentation = proj.fragmentation();
 	match proj {
 		ProjectileType::Basic { damage, speed, .. } => {
 			let bundles = aim_pos
@@ -215,11 +426,12 @@ fn create_projectile_batch(
 						damage * damage_multi,
 						PROJECTILE_LIFETIME,
 						PROJECTILE_SIZE,
-						PLAYER_PROJECTILE_GROUP,
-						Group::ALL ^ PLAYER_OWNED_GROUP,
+						groups.member,
+						groups.wide,
 						DEFAULT_MAX_CONTACT,
 						DEFAULT_DRAG,
 						owner,
+						fragmentation,
 					)
 				})
 				.collect();
@@ -240,11 +452,12 @@ fn create_projectile_batch(
 						damage * damage_multi,
 						PROJECTILE_LIFETIME,
 						PROJECTILE_SIZE,
-						PLAYER_PROJECTILE_GROUP,
-						ENEMY_OWNED_GROUP,
+						groups.member,
+						groups.targets,
 						*penetration,
 						DEFAULT_DRAG,
 						owner,
+						fragmentation,
 					)
 				})
 				.collect();
@@ -266,11 +479,12 @@ fn create_projectile_batch(
 							damage * damage_multi,
 							PROJECTILE_LIFETIME,
 							PROJECTILE_SIZE,
-							PLAYER_PROJECTILE_GROUP,
-							ENEMY_OWNED_GROUP,
+							groups.member,
+							groups.targets,
 							*bounce_limit,
 							DEFAULT_DRAG,
 							owner,
+							fragmentation,
 						),
 						Restitution {
 							coefficient: 1.0,
@@ -288,8 +502,26 @@ fn create_projectile_batch(
 			drag,
 			explosive_range,
 			explosive_speed,
+			status,
+			payload,
 			..
 		} => {
+			let mut scatter = DeathScatter::from(ScatterStage {
+				damage: damage * damage_multi,
+				pattern: ScatterPattern::Explosion {
+					range: *explosive_range,
+					speed: *explosive_speed,
+				},
+				status: *status,
+				knockback: EXPLOSION_KNOCKBACK,
+				..default()
+			});
+			if let Some(payload) = payload {
+				scatter.stages.push(ScatterStage {
+					damage: payload.damage * damage_multi,
+					..*payload
+				});
+			}
 			let bundles = aim_pos
 				.iter()
 				.map(|(aim, pos)| {
@@ -299,24 +531,48 @@ fn create_projectile_batch(
 						0.0,
 						PROJECTILE_LIFETIME,
 						PROJECTILE_SIZE,
-						PLAYER_PROJECTILE_GROUP,
-						ENEMY_OWNED_GROUP,
+						groups.member,
+						groups.targets,
 						*bounce_limit,
 						*drag,
-						DeathScatter {
-							damage: damage * damage_multi,
-							pattern: ScatterPattern::Explosion {
-								range: *explosive_range,
-								speed: *explosive_speed,
-							},
-							..default()
-						},
+						scatter.clone(),
 						owner,
+						fragmentation,
 					)
 				})
 				.collect();
 			ProjBatch::Scatter(bundles)
 		}
+		ProjectileType::Homing {
+			damage,
+			speed,
+			turn_rate,
+			range,
+			..
+		} => {
+			let bundles = aim_pos
+				.iter()
+				.map(|(aim, pos)| {
+					HomingProj(
+						fire_projectile(
+							*pos,
+							aim * speed * speed_multi,
+							damage * damage_multi,
+							PROJECTILE_LIFETIME,
+							PROJECTILE_SIZE,
+							groups.member,
+							groups.wide,
+							DEFAULT_MAX_CONTACT,
+							DEFAULT_DRAG,
+							owner,
+							fragmentation,
+						),
+						Homing::new(*turn_rate, *range, owner),
+					)
+				})
+				.collect();
+			ProjBatch::Homing(bundles)
+		}
 	}
 }
 
@@ -361,6 +617,7 @@ fn fire_projectile(
 	max_contact: u32,
 	drag: f32,
 	owner: Owner,
+	fragmentation: Fragmentation,
 ) -> ProjBundle {
 	ProjBundle {
 		proj: owner.into(),
@@ -377,6 +634,8 @@ fn fire_projectile(
 			linear_damping: drag,
 			..default()
 		},
+		knockback: Knockback(PROJECTILE_KNOCKBACK),
+		fragmentation,
 	}
 }
 
@@ -392,6 +651,7 @@ fn fire_scatter_projectile(
 	drag: f32,
 	scatter: DeathScatter,
 	owner: Owner,
+	fragmentation: Fragmentation,
 ) -> ScatterProj {
 	ScatterProj(
 		fire_projectile(
@@ -405,6 +665,7 @@ fn fire_scatter_projectile(
 			max_contact,
 			drag,
 			owner,
+			fragmentation,
 		),
 		scatter,
 	)
@@ -421,6 +682,7 @@ fn fire_sensor_projectile(
 	max_contact: u32,
 	drag: f32,
 	owner: Owner,
+	fragmentation: Fragmentation,
 ) -> SensorProj {
 	SensorProj(
 		fire_projectile(
@@ -434,6 +696,7 @@ fn fire_sensor_projectile(
 			max_contact,
 			drag,
 			owner,
+			fragmentation,
 		),
 		Sensor,
 	)
@@ -6,3 +6,32 @@ pub struct ExplosionMeshData {
 	pub mesh: Handle<Mesh>,
 	pub material: Handle<ColorMaterial>,
 }
+
+//Translucent copy of the player's color for the dash trail
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct AfterimageMaterial(pub Handle<ColorMaterial>);
+
+//Freezes the game for a moment when the player gets hit
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct HitStop {
+	pub duration: f32,
+	//Counts down in real time while virtual time is paused
+	pub timer: Option<Timer>,
+}
+
+impl Default for HitStop {
+	fn default() -> Self {
+		Self {
+			duration: 0.06,
+			timer: None,
+		}
+	}
+}
+
+impl HitStop {
+	pub fn trigger(&mut self) {
+		self.timer = Some(Timer::from_seconds(self.duration, TimerMode::Once));
+	}
+}
@@ -0,0 +1,45 @@
+use bevy::{
+	asset::{AssetLoader, LoadContext, io::Reader, ron},
+	platform::collections::HashMap,
+	prelude::*,
+};
+
+#[derive(Asset, TypePath)]
+pub struct EnemyRoster {
+	pub enemies: Vec<Handle<DynamicScene>>,
+}
+
+#[derive(Resource)]
+pub struct EnemyRosterHandle(pub Handle<EnemyRoster>);
+
+//A roster is a RON list of enemy definition scenes, e.g. ["enemies/chaser.scn.ron"]
+#[derive(Default)]
+pub struct EnemyRosterLoader;
+
+impl AssetLoader for EnemyRosterLoader {
+	type Asset = EnemyRoster;
+	type Settings = ();
+	type Error = Box<dyn std::error::Error + Send + Sync>;
+
+	async fn load(
+		&self,
+		reader: &mut dyn Reader,
+		_settings: &(),
+		load_context: &mut LoadContext<'_>,
+	) -> Result<Self::Asset, Self::Error> {
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes).await?;
+		let paths: Vec<String> = ron::de::from_bytes(&bytes)?;
+		let enemies = paths.into_iter().map(|path| load_context.load(path)).collect();
+		return Ok(EnemyRoster { enemies });
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["roster.ron"]
+	}
+}
+
+//Disabled prefab entities built from the roster, keyed by archetype
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct EnemyPrefabs(pub HashMap<String, Entity>);
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+
+use bevy::{asset::ron, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::resources::replay::InputFrame;
+
+const CONFIG_PATH: &str = "config/input.ron";
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Reflect, Serialize, Deserialize)]
+pub enum Action {
+	MoveUp,
+	MoveDown,
+	MoveLeft,
+	MoveRight,
+	Fire,
+	//Slot 1-5 of the default projectile types
+	SelectProjectile(u8),
+	NextProjectile,
+	PrevProjectile,
+	//Slot 1-4 of the weapon inventory
+	SelectWeapon(u8),
+	NextWeapon,
+	Dash,
+	//Choice 1-3 of a level-up draft
+	PickUpgrade(u8),
+	Start,
+	Restart,
+	RandomSeed,
+	SeedUp,
+	SeedDown,
+}
+
+impl Action {
+	pub fn context(&self) -> ActionContext {
+		match self {
+			Action::PickUpgrade(_) => ActionContext::Draft,
+			Action::Start | Action::RandomSeed | Action::SeedUp | Action::SeedDown => ActionContext::Menu,
+			Action::Restart => ActionContext::GameOver,
+			_ => ActionContext::Play,
+		}
+	}
+}
+
+//Where an action is read, actions from different contexts are free to share a binding
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ActionContext {
+	Play,
+	//Play actions are ignored while a level-up draft is open
+	Draft,
+	Menu,
+	GameOver,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Reflect, Serialize, Deserialize)]
+pub enum Binding {
+	Key(KeyCode),
+	Mouse(MouseButton),
+	Gamepad(GamepadButton),
+}
+
+impl Binding {
+	pub fn label(&self) -> String {
+		return match self {
+			Binding::Key(KeyCode::Space) => "SPACE".into(),
+			Binding::Key(key) => {
+				let name = format!("{key:?}");
+				let name = name.strip_prefix("Key").or(name.strip_prefix("Digit")).unwrap_or(&name);
+				name.to_uppercase()
+			}
+			Binding::Mouse(button) => format!("MOUSE {button:?}").to_uppercase(),
+			Binding::Gamepad(button) => format!("PAD {button:?}").to_uppercase(),
+		};
+	}
+}
+
+//Which bindings trigger each action, loaded from and saved to a RON config file
+#[derive(Resource, Reflect, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct ActionMap(pub BTreeMap<Action, Vec<Binding>>);
+
+impl Default for ActionMap {
+	fn default() -> Self {
+		return Self(BTreeMap::from([
+			(Action::MoveUp, vec![Binding::Key(KeyCode::KeyW)]),
+			(Action::MoveDown, vec![Binding::Key(KeyCode::KeyS)]),
+			(Action::MoveLeft, vec![Binding::Key(KeyCode::KeyA)]),
+			(Action::MoveRight, vec![Binding::Key(KeyCode::KeyD)]),
+			(
+				Action::Fire,
+				vec![
+					Binding::Mouse(MouseButton::Left),
+					Binding::Gamepad(GamepadButton::RightTrigger2),
+				],
+			),
+			(Action::SelectProjectile(1), vec![Binding::Key(KeyCode::Digit1)]),
+			(Action::SelectProjectile(2), vec![Binding::Key(KeyCode::Digit2)]),
+			(Action::SelectProjectile(3), vec![Binding::Key(KeyCode::Digit3)]),
+			(Action::SelectProjectile(4), vec![Binding::Key(KeyCode::Digit4)]),
+			(Action::SelectProjectile(5), vec![Binding::Key(KeyCode::Digit5)]),
+			(
+				Action::NextProjectile,
+				vec![
+					Binding::Key(KeyCode::KeyE),
+					Binding::Gamepad(GamepadButton::RightTrigger),
+				],
+			),
+			(
+				Action::PrevProjectile,
+				vec![
+					Binding::Key(KeyCode::KeyQ),
+					Binding::Gamepad(GamepadButton::LeftTrigger),
+				],
+			),
+			(Action::SelectWeapon(1), vec![Binding::Key(KeyCode::KeyZ)]),
+			(Action::SelectWeapon(2), vec![Binding::Key(KeyCode::KeyX)]),
+			(Action::SelectWeapon(3), vec![Binding::Key(KeyCode::KeyC)]),
+			(Action::SelectWeapon(4), vec![Binding::Key(KeyCode::KeyV)]),
+			(
+				Action::NextWeapon,
+				vec![Binding::Key(KeyCode::Tab), Binding::Gamepad(GamepadButton::West)],
+			),
+			(
+				Action::Dash,
+				vec![
+					Binding::Key(KeyCode::ShiftLeft),
+					Binding::Gamepad(GamepadButton::LeftTrigger2),
+				],
+			),
+			(
+				Action::PickUpgrade(1),
+				vec![Binding::Key(KeyCode::Digit1), Binding::Gamepad(GamepadButton::DPadLeft)],
+			),
+			(
+				Action::PickUpgrade(2),
+				vec![Binding::Key(KeyCode::Digit2), Binding::Gamepad(GamepadButton::DPadUp)],
+			),
+			(
+				Action::PickUpgrade(3),
+				vec![
+					Binding::Key(KeyCode::Digit3),
+					Binding::Gamepad(GamepadButton::DPadRight),
+				],
+			),
+			(
+				Action::Start,
+				vec![
+					Binding::Key(KeyCode::Space),
+					Binding::Gamepad(GamepadButton::Start),
+					Binding::Gamepad(GamepadButton::South),
+				],
+			),
+			(
+				Action::Restart,
+				vec![
+					Binding::Key(KeyCode::KeyR),
+					Binding::Gamepad(GamepadButton::Start),
+					Binding::Gamepad(GamepadButton::South),
+				],
+			),
+			(
+				Action::RandomSeed,
+				vec![Binding::Key(KeyCode::KeyN), Binding::Gamepad(GamepadButton::North)],
+			),
+			(
+				Action::SeedUp,
+				vec![Binding::Key(KeyCode::ArrowUp), Binding::Gamepad(GamepadButton::DPadUp)],
+			),
+			(
+				Action::SeedDown,
+				vec![
+					Binding::Key(KeyCode::ArrowDown),
+					Binding::Gamepad(GamepadButton::DPadDown),
+				],
+			),
+		]));
+	}
+}
+
+impl ActionMap {
+	pub fn bindings(&self, action: Action) -> &[Binding] {
+		return self.0.get(&action).map(Vec::as_slice).unwrap_or_default();
+	}
+
+	pub fn rebind(&mut self, action: Action, bindings: Vec<Binding>) {
+		self.0.insert(action, bindings);
+	}
+
+	//Name of the first binding, for prompts like "Press [SPACE] to Start"
+	pub fn label(&self, action: Action) -> String {
+		return self
+			.bindings(action)
+			.first()
+			.map(Binding::label)
+			.unwrap_or_else(|| "UNBOUND".into());
+	}
+
+	//Bindings shared by two actions that are read at the same time, either would fire on the one press
+	pub fn clashes(&self) -> Vec<(Action, Action, Binding)> {
+		let mut clashes = Vec::new();
+		for (i, (action, bindings)) in self.0.iter().enumerate() {
+			for (other, other_bindings) in self.0.iter().skip(i + 1) {
+				if action.context() != other.context() {
+					continue;
+				}
+				clashes.extend(
+					bindings
+						.iter()
+						.filter(|binding| other_bindings.contains(binding))
+						.map(|binding| (*action, *other, *binding)),
+				);
+			}
+		}
+		return clashes;
+	}
+
+	//Actions missing from the file, e.g. ones added since it was written, keep their default bindings
+	pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+		let mut map: Self = ron::de::from_bytes(&std::fs::read(path)?)?;
+		for (action, bindings) in Self::default().0 {
+			map.0.entry(action).or_insert(bindings);
+		}
+		for (action, other, binding) in map.clashes() {
+			warn!(
+				"{} is bound to both {action:?} and {other:?} in {path}",
+				binding.label()
+			);
+		}
+		return Ok(map);
+	}
+
+	pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+		if let Some(dir) = std::path::Path::new(path).parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		let config = ron::ser::to_string_pretty(self, default())?;
+		std::fs::write(path, config)?;
+		return Ok(());
+	}
+
+	//Falls back to the defaults and writes them out so there is a file to edit
+	pub fn load_or_create() -> Self {
+		match Self::load(CONFIG_PATH) {
+			Ok(map) => return map,
+			Err(err) => info!("Using default bindings, could not load {CONFIG_PATH}: {err}"),
+		}
+		let map = Self::default();
+		if let Err(err) = map.save(CONFIG_PATH) {
+			warn!("Failed to save bindings to {CONFIG_PATH}: {err}");
+		}
+		return map;
+	}
+}
+
+//Reads actions through the action map instead of polling raw keys and buttons
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+	pub map: Res<'w, ActionMap>,
+	keys: Res<'w, ButtonInput<KeyCode>>,
+	mouse: Res<'w, ButtonInput<MouseButton>>,
+	gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+impl ActionInput<'_, '_> {
+	pub fn pressed(&self, action: Action) -> bool {
+		return self.map.bindings(action).iter().any(|binding| match binding {
+			Binding::Key(key) => self.keys.pressed(*key),
+			Binding::Mouse(button) => self.mouse.pressed(*button),
+			Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.pressed(*button)),
+		});
+	}
+
+	pub fn just_pressed(&self, action: Action) -> bool {
+		return self.map.bindings(action).iter().any(|binding| match binding {
+			Binding::Key(key) => self.keys.just_pressed(*key),
+			Binding::Mouse(button) => self.mouse.just_pressed(*button),
+			Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.just_pressed(*button)),
+		});
+	}
+}
+
+//Twin-stick tuning for gamepads
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct StickSettings {
+	//Stick deflection below this is ignored, the rest is rescaled to start from zero
+	pub deadzone: f32,
+	//Enemies within this angle (radians) of the aim stick and within range pull the aim towards them
+	pub assist_angle: f32,
+	pub assist_range: f32,
+	//0 leaves the aim alone, 1 snaps it onto the enemy
+	pub assist_strength: f32,
+}
+
+impl Default for StickSettings {
+	fn default() -> Self {
+		Self {
+			deadzone: 0.2,
+			assist_angle: 0.35,
+			assist_range: 700.,
+			assist_strength: 0.8,
+		}
+	}
+}
+
+impl StickSettings {
+	pub fn apply_deadzone(&self, stick: Vec2) -> Vec2 {
+		let len = stick.length();
+		if len <= self.deadzone {
+			return Vec2::ZERO;
+		}
+		let scaled = ((len - self.deadzone) / (1. - self.deadzone)).min(1.);
+		return stick / len * scaled;
+	}
+
+	pub fn assist(&self, aim: Vec2, pos: Vec2, enemies: impl Iterator<Item = Vec2>) -> Vec2 {
+		let closest = enemies
+			.map(|enemy| enemy - pos)
+			.filter(|to_enemy| to_enemy.length() <= self.assist_range)
+			.map(|to_enemy| aim.angle_to(to_enemy))
+			.filter(|angle| angle.abs() <= self.assist_angle)
+			.min_by(|a, b| a.abs().total_cmp(&b.abs()));
+		return match closest {
+			Some(angle) => Vec2::from_angle(angle * self.assist_strength).rotate(aim),
+			None => aim,
+		};
+	}
+}
+
+//Where the player's intent comes from each tick
+#[derive(Resource, Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[reflect(Resource)]
+pub enum InputSource {
+	//Keyboard, mouse and gamepad
+	#[default]
+	Live,
+	//Frames from the ReplayPlayback resource
+	Replay,
+	//Plays itself, handy for soak testing
+	Bot,
+}
+
+//Live devices sampled every frame, consumed once per fixed tick
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct LiveInput {
+	pub move_dir: Vec2,
+	pub aim_dir: Vec2,
+	pub fire: bool,
+	//Latched until a tick picks it up so short presses between ticks are not lost
+	pub select: u8,
+	pub dash: bool,
+	pub pick: u8,
+	pub weapon: u8,
+	pub next_weapon: bool,
+	//Last picked slot, what the shoulder buttons cycle from
+	pub slot: u8,
+	//Set while the right stick aims, until the mouse moves again
+	pub stick_aim: bool,
+}
+
+impl Default for LiveInput {
+	fn default() -> Self {
+		Self {
+			move_dir: Vec2::ZERO,
+			aim_dir: Vec2::Y,
+			fire: false,
+			select: 0,
+			dash: false,
+			pick: 0,
+			weapon: 0,
+			next_weapon: false,
+			slot: 1,
+			stick_aim: false,
+		}
+	}
+}
+
+//What the active input source produced for the current fixed tick
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct TickInput(pub InputFrame);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn default_bindings_do_not_clash() {
+		assert_eq!(ActionMap::default().clashes(), Vec::new());
+	}
+
+	#[test]
+	fn shared_bindings_only_clash_within_a_context() {
+		let mut map = ActionMap::default();
+		map.rebind(Action::Dash, vec![Binding::Key(KeyCode::KeyW)]);
+		map.rebind(Action::Restart, vec![Binding::Key(KeyCode::KeyS)]);
+		assert_eq!(
+			map.clashes(),
+			vec![(Action::MoveUp, Action::Dash, Binding::Key(KeyCode::KeyW))]
+		);
+	}
+}
@@ -1,3 +1,8 @@
 pub mod audio;
 pub mod effects;
+pub mod enemies;
+pub mod input;
+pub mod replay;
+pub mod upgrades;
 pub mod utils;
+pub mod waves;
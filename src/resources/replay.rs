@@ -0,0 +1,267 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+const MAGIC: &[u8; 4] = b"CTRP";
+const VERSION: u8 = 2;
+const FRAME_SIZE: usize = 6;
+//Version 1 frames end before the weapon byte
+const V1_FRAME_SIZE: usize = 5;
+//The weapon slot shares its byte with the next weapon flag
+const MAX_WEAPON_SLOT: u8 = 0xf;
+const DEFAULT_PATH: &str = "replays/last.replay";
+
+//One fixed tick of player intent, quantized so a replay feeds back exactly what was simulated
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Reflect)]
+pub struct InputFrame {
+	pub move_x: i8,
+	pub move_y: i8,
+	//Angle of the aim direction, a full turn spread over the u16 range
+	pub aim: u16,
+	pub fire: bool,
+	//0 keeps the current projectile, 1-5 picks one of the default projectile types, packed into 3 bits
+	pub select: u8,
+	pub dash: bool,
+	//0 for none, 1-3 picks a choice of an open level-up draft
+	pub pick: u8,
+	//0 keeps the current weapon, otherwise a slot of the weapon inventory
+	pub weapon: u8,
+	pub next_weapon: bool,
+}
+
+impl InputFrame {
+	pub fn new(move_dir: Vec2, aim_dir: Vec2, fire: bool) -> Self {
+		let move_dir = move_dir.clamp_length_max(1.);
+		let angle = aim_dir.to_angle().rem_euclid(TAU);
+		return Self {
+			move_x: (move_dir.x * i8::MAX as f32).round() as i8,
+			move_y: (move_dir.y * i8::MAX as f32).round() as i8,
+			aim: ((angle / TAU) * 65536.).round() as u32 as u16,
+			fire,
+			..default()
+		};
+	}
+
+	pub fn move_dir(&self) -> Vec2 {
+		let dir = Vec2::new(self.move_x as f32, self.move_y as f32) / i8::MAX as f32;
+		return dir.clamp_length_max(1.);
+	}
+
+	pub fn aim_dir(&self) -> Vec2 {
+		return Vec2::from_angle(self.aim as f32 / 65536. * TAU);
+	}
+
+	fn write(&self, bytes: &mut Vec<u8>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+		if self.weapon > MAX_WEAPON_SLOT {
+			return Err(format!("Weapon slot {} doesn't fit in a replay frame", self.weapon).into());
+		}
+		bytes.push(self.move_x as u8);
+		bytes.push(self.move_y as u8);
+		bytes.extend_from_slice(&self.aim.to_le_bytes());
+		//Pick and dash take bits older replays always left clear
+		bytes.push(self.fire as u8 | (self.select << 1) | (self.pick << 4) | ((self.dash as u8) << 7));
+		bytes.push(self.weapon | ((self.next_weapon as u8) << 4));
+		return Ok(());
+	}
+
+	//Takes exactly one frame, older versions leave out the bytes added since
+	fn read(bytes: &[u8]) -> Self {
+		let weapon = bytes.get(5).copied().unwrap_or_default();
+		return Self {
+			move_x: bytes[0] as i8,
+			move_y: bytes[1] as i8,
+			aim: u16::from_le_bytes([bytes[2], bytes[3]]),
+			fire: bytes[4] & 1 != 0,
+			select: (bytes[4] >> 1) & 0x7,
+			pick: (bytes[4] >> 4) & 0x3,
+			dash: bytes[4] & 0x80 != 0,
+			weapon: weapon & MAX_WEAPON_SLOT,
+			next_weapon: weapon & 0x10 != 0,
+		};
+	}
+}
+
+//A run is its seed plus the input of every fixed tick
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Replay {
+	pub seed: u64,
+	pub frames: Vec<InputFrame>,
+}
+
+impl Replay {
+	//Header is magic, version and seed, followed by run length encoded frames as (u16 count, frame)
+	pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(MAGIC);
+		bytes.push(VERSION);
+		bytes.extend_from_slice(&self.seed.to_le_bytes());
+		for run in self.frames.chunk_by(|a, b| a == b) {
+			for chunk in run.chunks(u16::MAX as usize) {
+				bytes.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+				chunk[0].write(&mut bytes)?;
+			}
+		}
+		return Ok(bytes);
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+		let Some((header, mut body)) = bytes.split_at_checked(13) else {
+			return Err("Replay is missing its header".into());
+		};
+		if &header[..4] != MAGIC {
+			return Err("Not a replay file".into());
+		}
+		let frame_size = match header[4] {
+			1 => V1_FRAME_SIZE,
+			VERSION => FRAME_SIZE,
+			version => return Err(format!("Unsupported replay version {version}").into()),
+		};
+		let seed = u64::from_le_bytes(header[5..13].try_into()?);
+		let mut frames = Vec::new();
+		while !body.is_empty() {
+			if body.len() < 2 + frame_size {
+				return Err("Replay ends mid frame".into());
+			}
+			let count = u16::from_le_bytes([body[0], body[1]]) as usize;
+			let frame = InputFrame::read(&body[2..2 + frame_size]);
+			frames.extend(std::iter::repeat_n(frame, count));
+			body = &body[2 + frame_size..];
+		}
+		return Ok(Self { seed, frames });
+	}
+
+	pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+		return Self::from_bytes(&std::fs::read(path)?);
+	}
+
+	pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+		let bytes = self.to_bytes()?;
+		if let Some(dir) = std::path::Path::new(path).parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		std::fs::write(path, bytes)?;
+		return Ok(());
+	}
+}
+
+//The run being recorded, saved to `path` on game over unless there is none
+#[derive(Resource)]
+pub struct ReplayRecorder {
+	pub path: Option<String>,
+	pub replay: Replay,
+}
+
+impl Default for ReplayRecorder {
+	fn default() -> Self {
+		Self {
+			path: Some(DEFAULT_PATH.into()),
+			replay: default(),
+		}
+	}
+}
+
+//Present when a replay is fed back in place of live input
+#[derive(Resource)]
+pub struct ReplayPlayback {
+	pub replay: Replay,
+	pub tick: usize,
+}
+
+impl ReplayPlayback {
+	pub fn new(replay: Replay) -> Self {
+		Self { replay, tick: 0 }
+	}
+
+	//Once the recording runs out the player stands still, still facing where they last aimed
+	pub fn next_frame(&mut self) -> InputFrame {
+		let frames = &self.replay.frames;
+		let frame = match frames.get(self.tick) {
+			Some(frame) => *frame,
+			None => InputFrame {
+				aim: frames.last().map(|frame| frame.aim).unwrap_or_default(),
+				..default()
+			},
+		};
+		self.tick += 1;
+		return frame;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn replay_round_trips_through_bytes() {
+		let moving = InputFrame {
+			select: 3,
+			dash: true,
+			pick: 2,
+			weapon: 15,
+			next_weapon: true,
+			..InputFrame::new(Vec2::new(0.6, -0.8), Vec2::NEG_X, true)
+		};
+		let mut frames = vec![InputFrame::default(); 3];
+		//Long enough to be split across several runs
+		frames.extend(std::iter::repeat_n(moving, u16::MAX as usize + 10));
+		frames.push(InputFrame::new(Vec2::ZERO, Vec2::Y, false));
+		let replay = Replay {
+			seed: 0xdead_beef,
+			frames,
+		};
+		assert_eq!(Replay::from_bytes(&replay.to_bytes().unwrap()).unwrap(), replay);
+	}
+
+	#[test]
+	fn version_1_replays_still_load() {
+		let mut bytes = MAGIC.to_vec();
+		bytes.push(1);
+		bytes.extend_from_slice(&42u64.to_le_bytes());
+		//Two frames moving right while firing with the second projectile picked, then one standing still
+		bytes.extend_from_slice(&2u16.to_le_bytes());
+		bytes.extend_from_slice(&[i8::MAX as u8, 0, 0, 0, 1 | (2 << 1)]);
+		bytes.extend_from_slice(&1u16.to_le_bytes());
+		bytes.extend_from_slice(&[0, 0, 0, 0, 0]);
+		let replay = Replay::from_bytes(&bytes).unwrap();
+		assert_eq!(replay.seed, 42);
+		assert_eq!(replay.frames.len(), 3);
+		assert_eq!(replay.frames[0], replay.frames[1]);
+		assert_eq!(replay.frames[0].move_dir(), Vec2::X);
+		assert!(replay.frames[0].fire);
+		assert_eq!(replay.frames[0].select, 2);
+		assert_eq!(replay.frames[0].weapon, 0);
+		assert_eq!(replay.frames[2], InputFrame::default());
+	}
+
+	#[test]
+	fn weapon_slots_past_the_nibble_are_refused() {
+		let frame = InputFrame {
+			weapon: MAX_WEAPON_SLOT + 1,
+			..default()
+		};
+		let replay = Replay {
+			seed: 0,
+			frames: vec![frame],
+		};
+		let err = replay.to_bytes().unwrap_err();
+		assert!(err.to_string().contains("doesn't fit"), "Failed with {err}");
+	}
+
+	#[test]
+	fn broken_replays_are_rejected() {
+		let bytes = Replay::default().to_bytes().unwrap();
+		assert!(Replay::from_bytes(&bytes[..8]).is_err());
+		assert!(Replay::from_bytes(b"NOPE000000000").is_err());
+		let mut future = bytes.clone();
+		future[4] = VERSION + 1;
+		assert!(Replay::from_bytes(&future).is_err());
+		let mut truncated = Replay {
+			seed: 1,
+			frames: vec![InputFrame::default()],
+		}
+		.to_bytes()
+		.unwrap();
+		truncated.pop();
+		assert!(Replay::from_bytes(&truncated).is_err());
+	}
+}
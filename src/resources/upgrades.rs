@@ -0,0 +1,188 @@
+use bevy::{
+	asset::{AssetLoader, LoadContext, io::Reader, ron},
+	platform::collections::HashMap,
+	prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+	components::{
+		death::ScatterStage,
+		stats::{ModifierOp, StatKind},
+		status::StatusApplication,
+		weapons::Fragmentation,
+	},
+	resources::utils::ProjectileSlot,
+};
+
+//Every upgrade a level-up draft can offer
+#[derive(Asset, TypePath, Deserialize)]
+pub struct UpgradePool {
+	pub upgrades: Vec<UpgradeDefinition>,
+}
+
+#[derive(Reflect, Deserialize, Clone)]
+pub struct UpgradeDefinition {
+	pub name: String,
+	pub rarity: Rarity,
+	//How many times it can be picked in a run, 0 for no limit
+	#[serde(default)]
+	pub max_picks: u32,
+	pub effects: Vec<UpgradeEffect>,
+}
+
+impl UpgradeDefinition {
+	pub fn description(&self) -> String {
+		return self
+			.effects
+			.iter()
+			.map(UpgradeEffect::describe)
+			.collect::<Vec<_>>()
+			.join(", ");
+	}
+}
+
+#[derive(Reflect, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rarity {
+	Common,
+	Uncommon,
+	Rare,
+	Epic,
+}
+
+impl Rarity {
+	//Relative odds of being offered
+	pub fn weight(&self) -> f32 {
+		match self {
+			Rarity::Common => 60.,
+			Rarity::Uncommon => 25.,
+			Rarity::Rare => 10.,
+			Rarity::Epic => 3.,
+		}
+	}
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			Rarity::Common => "Common",
+			Rarity::Uncommon => "Uncommon",
+			Rarity::Rare => "Rare",
+			Rarity::Epic => "Epic",
+		}
+	}
+
+	pub fn color(&self) -> Color {
+		match self {
+			Rarity::Common => Color::linear_rgb(0.8, 0.8, 0.8),
+			Rarity::Uncommon => Color::linear_rgb(0.2, 1.0, 0.3),
+			Rarity::Rare => Color::linear_rgb(0.2, 0.5, 1.0),
+			Rarity::Epic => Color::linear_rgb(0.8, 0.2, 1.0),
+		}
+	}
+}
+
+#[derive(Reflect, Deserialize, Clone, Copy)]
+pub enum UpgradeEffect {
+	Stat {
+		stat: StatKind,
+		op: ModifierOp,
+	},
+	//Extra projectiles per shot for every projectile type
+	Multishot(u32),
+	//Turns a projectile type into a variant that applies a status
+	ProjectileStatus {
+		projectile: ProjectileSlot,
+		status: StatusApplication,
+	},
+	//Adds to the chance, damage and size of the explosions player projectiles leave where they hit
+	ScatterOnHit {
+		chance: f32,
+		damage: f32,
+		range: f32,
+	},
+	//Gives a projectile type fragments on hit, replacing any it already had
+	ProjectileFragmentation {
+		projectile: ProjectileSlot,
+		fragmentation: Fragmentation,
+	},
+	//Grenades set off this stage on top of their explosion, a later pick replaces the earlier one
+	GrenadePayload(ScatterStage),
+}
+
+impl UpgradeEffect {
+	pub fn describe(&self) -> String {
+		match self {
+			UpgradeEffect::Stat { stat, op } => op.describe(*stat),
+			UpgradeEffect::Multishot(count) => format!("+{count} Projectiles per shot"),
+			UpgradeEffect::ProjectileStatus { projectile, status } => format!(
+				"{} shots apply {} {} for {}s",
+				projectile.label(),
+				status.stacks,
+				status.kind.label(),
+				status.duration
+			),
+			UpgradeEffect::ScatterOnHit { chance, damage, range } => format!(
+				"+{:.0}% chance for hits to explode for {damage} damage in {range} range",
+				chance * 100.
+			),
+			UpgradeEffect::ProjectileFragmentation {
+				projectile,
+				fragmentation,
+			} => format!(
+				"{} shots split into {} fragments for {:.0}% damage on hit",
+				projectile.label(),
+				fragmentation.count,
+				fragmentation.damage * 100.
+			),
+			UpgradeEffect::GrenadePayload(stage) => format!(
+				"Grenades release a {} of {} for {} damage",
+				stage.pattern.label(),
+				stage.count,
+				stage.damage
+			),
+		}
+	}
+}
+
+#[derive(Resource)]
+pub struct UpgradePoolHandle(pub Handle<UpgradePool>);
+
+#[derive(Default)]
+pub struct UpgradePoolLoader;
+
+impl AssetLoader for UpgradePoolLoader {
+	type Asset = UpgradePool;
+	type Settings = ();
+	type Error = Box<dyn std::error::Error + Send + Sync>;
+
+	async fn load(
+		&self,
+		reader: &mut dyn Reader,
+		_settings: &(),
+		_load_context: &mut LoadContext<'_>,
+	) -> Result<Self::Asset, Self::Error> {
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes).await?;
+		return Ok(ron::de::from_bytes(&bytes)?);
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["upgrades.ron"]
+	}
+}
+
+//Levels waiting to be drafted and the choices currently on offer, gameplay holds still while it is open
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct LevelUpDraft {
+	pub pending: u32,
+	//Indices into the upgrade pool, empty while closed
+	pub choices: Vec<usize>,
+	//How many times each upgrade has been picked this run
+	pub picks: HashMap<usize, u32>,
+}
+
+impl LevelUpDraft {
+	pub fn is_open(&self) -> bool {
+		!self.choices.is_empty()
+	}
+}
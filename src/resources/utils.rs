@@ -1,17 +1,45 @@
 use std::f32::consts::TAU;
 
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
 use rand::{
 	Rng,
 	distr::uniform::{SampleRange, SampleUniform},
 };
 use rand_chacha::ChaChaRng;
+use serde::Deserialize;
 
-use crate::components::weapons::ProjectileType;
+use crate::components::{death::ChainLink, elite::Affix, stats::Critical, tags::Owner, weapons::ProjectileType};
 
 #[derive(Resource)]
 pub struct RandomGen(pub ChaChaRng);
 
+#[derive(Resource, Reflect, Clone, Copy)]
+#[reflect(Resource)]
+pub struct RunSeed {
+	pub seed: u64,
+	//Set by the player, kept across runs instead of rolling a new one
+	pub locked: bool,
+}
+
+impl RunSeed {
+	pub fn random() -> Self {
+		Self {
+			seed: rand::random(),
+			locked: false,
+		}
+	}
+
+	pub fn fixed(seed: u64) -> Self {
+		Self { seed, locked: true }
+	}
+}
+
+impl Default for RunSeed {
+	fn default() -> Self {
+		Self::random()
+	}
+}
+
 impl RandomGen {
 	pub fn range<T, R>(&mut self, range: R) -> T
 	where
@@ -21,6 +49,10 @@ impl RandomGen {
 		self.0.random_range(range)
 	}
 
+	pub fn chance(&mut self, probability: f32) -> bool {
+		return self.0.random_range(0.0..1.0) < probability;
+	}
+
 	pub fn point_on_circle_vec3(&mut self, range: f32) -> Vec3 {
 		return self.point_on_circle_vec2(range).extend(0.0);
 	}
@@ -34,6 +66,25 @@ impl RandomGen {
 		let angle = self.0.random_range(0.0..TAU);
 		return Vec2::new(angle.cos(), angle.sin());
 	}
+
+	pub fn weighted_index(&mut self, weights: impl Iterator<Item = f32> + Clone) -> Option<usize> {
+		let total: f32 = weights.clone().sum();
+		if total <= 0. {
+			return None;
+		}
+		let mut roll = self.0.random_range(0.0..total);
+		let mut last = 0;
+		for (i, weight) in weights.enumerate() {
+			if roll < weight {
+				return Some(i);
+			}
+			roll -= weight;
+			if weight > 0. {
+				last = i;
+			}
+		}
+		return Some(last);
+	}
 }
 
 #[derive(Resource, Reflect)]
@@ -48,10 +99,95 @@ pub struct Fonts {
 #[reflect(Resource)]
 pub struct KillCount(pub u32);
 
+//Non-player deaths in a row that dropped nothing, each one makes the next drop a little likelier
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct DropPity(pub u32);
+
+const CHAIN_TIMEOUT: f32 = 3.;
+const COMBO_STEP: f32 = 0.1;
+
+#[derive(Reflect, Default, Clone, Copy)]
+pub struct Chain {
+	pub depth: u32,
+	pub length: u32,
+	pub last_kill: f32,
+}
+
+impl Chain {
+	pub fn multiplier(&self) -> f32 {
+		1. + self.length.saturating_sub(1) as f32 * COMBO_STEP
+	}
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ChainStats {
+	pub next_chain: u32,
+	pub active: HashMap<u32, Chain>,
+	pub longest: u32,
+	pub deepest: u32,
+	pub best_multiplier: f32,
+	pub chain_kills: u32,
+	pub score: f32,
+}
+
+impl Default for ChainStats {
+	fn default() -> Self {
+		Self {
+			next_chain: 0,
+			active: HashMap::default(),
+			longest: 0,
+			deepest: 0,
+			best_multiplier: 1.,
+			chain_kills: 0,
+			score: 0.,
+		}
+	}
+}
+
+impl ChainStats {
+	pub fn start_chain(&mut self) -> ChainLink {
+		let chain = self.next_chain;
+		self.next_chain += 1;
+		ChainLink { chain, generation: 0 }
+	}
+
+	pub fn record_kill(&mut self, link: ChainLink, time: f32) {
+		if link.is_enemy() {
+			return;
+		}
+		let chain = self.active.entry(link.chain).or_default();
+		chain.length += 1;
+		chain.depth = chain.depth.max(link.generation);
+		chain.last_kill = time;
+		let chain = *chain;
+		if chain.length > 1 {
+			self.chain_kills += 1;
+		}
+		self.longest = self.longest.max(chain.length);
+		self.deepest = self.deepest.max(chain.depth);
+		self.best_multiplier = self.best_multiplier.max(chain.multiplier());
+		self.score += chain.multiplier();
+	}
+
+	pub fn expire(&mut self, time: f32) {
+		self.active.retain(|_, chain| time - chain.last_kill < CHAIN_TIMEOUT);
+	}
+
+	//The biggest chain still going
+	pub fn current(&self) -> Option<&Chain> {
+		self.active.values().max_by_key(|chain| chain.length)
+	}
+}
+
 #[derive(Event)]
 pub struct DeathEvent {
 	pub pos: Vec2,
 	pub is_player: bool,
+	pub archetype: Option<String>,
+	//Empty unless an elite died
+	pub affixes: Vec<Affix>,
 }
 
 #[derive(Resource, Reflect)]
@@ -61,6 +197,7 @@ pub struct DefaultProjTypes {
 	pub piercing: ProjectileType,
 	pub bouncing: ProjectileType,
 	pub grenade: ProjectileType,
+	pub homing: ProjectileType,
 }
 
 impl DefaultProjTypes {
@@ -69,5 +206,148 @@ impl DefaultProjTypes {
 		self.piercing = self.piercing.upgrade(rate);
 		self.bouncing = self.bouncing.upgrade(rate);
 		self.grenade = self.grenade.upgrade(rate);
+		self.homing = self.homing.upgrade(rate);
+	}
+
+	pub fn get_mut(&mut self, slot: ProjectileSlot) -> &mut ProjectileType {
+		match slot {
+			ProjectileSlot::Basic => &mut self.basic,
+			ProjectileSlot::Piercing => &mut self.piercing,
+			ProjectileSlot::Bouncing => &mut self.bouncing,
+			ProjectileSlot::Grenade => &mut self.grenade,
+			ProjectileSlot::Homing => &mut self.homing,
+		}
+	}
+
+	pub fn all_mut(&mut self) -> [&mut ProjectileType; 5] {
+		[
+			&mut self.basic,
+			&mut self.piercing,
+			&mut self.bouncing,
+			&mut self.grenade,
+			&mut self.homing,
+		]
+	}
+
+	//The default of the same kind, so a selected projectile picks up changes made to the defaults
+	pub fn matching(&self, proj_type: &ProjectileType) -> ProjectileType {
+		match proj_type {
+			ProjectileType::Basic { .. } => self.basic,
+			ProjectileType::Piercing { .. } => self.piercing,
+			ProjectileType::Bouncing { .. } => self.bouncing,
+			ProjectileType::Grenade { .. } => self.grenade,
+			ProjectileType::Homing { .. } => self.homing,
+		}
+	}
+}
+
+#[derive(Reflect, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProjectileSlot {
+	Basic,
+	Piercing,
+	Bouncing,
+	Grenade,
+	Homing,
+}
+
+impl ProjectileSlot {
+	pub fn label(&self) -> &'static str {
+		match self {
+			ProjectileSlot::Basic => "Basic",
+			ProjectileSlot::Piercing => "Piercing",
+			ProjectileSlot::Bouncing => "Bouncing",
+			ProjectileSlot::Grenade => "Grenade",
+			ProjectileSlot::Homing => "Homing",
+		}
+	}
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageKind {
+	Kinetic,
+	Explosive,
+	Energy,
+	Contact,
+	//Poison over time
+	Toxic,
+}
+
+#[derive(Event)]
+pub struct DamageEvent {
+	//Entity dealing the damage, the projectile, explosion or attacker
+	pub source: Entity,
+	pub target: Entity,
+	pub amount: f32,
+	pub kind: DamageKind,
+	//Rolled when the hit lands
+	pub crit: Critical,
+	//Where the hit came from, the target is pushed away from it
+	pub origin: Vec2,
+	pub knockback: f32,
+	//Side that gets credit for the damage
+	pub instigator: Owner,
+}
+
+//Playable area, matches the window when there is one
+#[derive(Resource, Reflect, Clone, Copy)]
+#[reflect(Resource)]
+pub struct ArenaSize(pub Vec2);
+
+impl Default for ArenaSize {
+	fn default() -> Self {
+		Self(Vec2::new(1920., 1080.))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::SeedableRng;
+
+	use super::*;
+
+	fn rng() -> RandomGen {
+		return RandomGen(ChaChaRng::seed_from_u64(0));
+	}
+
+	#[test]
+	fn weighted_index_never_picks_zero_weights() {
+		let mut rng = rng();
+		for _ in 0..1000 {
+			let index = rng.weighted_index([0., 1., 0., 2., 0.].into_iter());
+			assert!(matches!(index, Some(1 | 3)), "Picked {index:?}");
+		}
+	}
+
+	#[test]
+	fn weighted_index_follows_the_weights() {
+		let mut rng = rng();
+		let mut counts = [0; 3];
+		for _ in 0..10000 {
+			counts[rng.weighted_index([1., 2., 7.].into_iter()).unwrap()] += 1;
+		}
+		let share = counts[2] as f32 / 10000.;
+		assert!((share - 0.7).abs() < 0.03, "Heaviest weight picked {share} of the time");
+		assert!(counts[0] < counts[1], "Picks were {counts:?}");
+	}
+
+	#[test]
+	fn weighted_index_is_none_without_any_weight() {
+		let mut rng = rng();
+		assert_eq!(rng.weighted_index([0., 0.].into_iter()), None);
+		assert_eq!(rng.weighted_index(std::iter::empty()), None);
+	}
+
+	#[test]
+	fn enemy_links_never_count_towards_a_chain() {
+		let mut chains = ChainStats::default();
+		chains.record_kill(ChainLink::ENEMY, 0.);
+		chains.record_kill(ChainLink::ENEMY.next(), 0.);
+		assert!(chains.active.is_empty());
+		assert_eq!(chains.score, 0.);
+
+		let link = chains.start_chain();
+		chains.record_kill(link, 0.);
+		chains.record_kill(link.next(), 0.);
+		assert_eq!(chains.longest, 2);
 	}
 }
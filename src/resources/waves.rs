@@ -0,0 +1,174 @@
+use bevy::{
+	asset::{AssetLoader, LoadContext, io::Reader, ron},
+	prelude::*,
+};
+use serde::Deserialize;
+
+use crate::components::spawner::SpawnEntry;
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct WaveScript {
+	pub waves: Vec<WaveDefinition>,
+	pub endless: EndlessDefinition,
+}
+
+#[derive(Reflect, Deserialize, Clone)]
+pub struct WaveDefinition {
+	//Seconds to wait before the wave starts
+	pub delay: f32,
+	pub count: u32,
+	pub min_batch: u32,
+	pub max_batch: u32,
+	//Seconds between batches
+	pub batch_interval: f32,
+	pub spawners: Vec<SpawnPoint>,
+	pub enemies: Vec<SpawnEntry>,
+	//Archetype of a boss that shows up as the wave starts
+	#[serde(default)]
+	pub boss: Option<String>,
+}
+
+#[derive(Reflect, Deserialize, Clone, Copy)]
+pub struct SpawnPoint {
+	//Degrees
+	pub angle: f32,
+	pub distance: f32,
+}
+
+//Waves generated once the script runs out, scaled by the director's intensity
+#[derive(Reflect, Deserialize, Clone)]
+pub struct EndlessDefinition {
+	pub delay: f32,
+	pub base_count: u32,
+	pub count_per_wave: u32,
+	pub min_batch: u32,
+	pub max_batch: u32,
+	pub batch_interval: f32,
+	pub spawner_count: u32,
+	pub spawner_distance: f32,
+	//Kills per second considered to be keeping up
+	pub target_kill_rate: f32,
+	pub enemies: Vec<SpawnEntry>,
+	//Every this many endless waves one of the bosses takes its turn, 0 for none
+	#[serde(default)]
+	pub boss_every: u32,
+	#[serde(default)]
+	pub bosses: Vec<String>,
+}
+
+impl EndlessDefinition {
+	pub fn generate(&self, wave: u32, intensity: f32) -> WaveDefinition {
+		let count = (self.base_count + self.count_per_wave * wave) as f32 * intensity;
+		let max_batch = (self.max_batch as f32 * intensity).round() as u32;
+		let angle = 360. / self.spawner_count.max(1) as f32;
+		let boss = if self.boss_every > 0 && (wave + 1).is_multiple_of(self.boss_every) && !self.bosses.is_empty() {
+			let milestone = (wave + 1) / self.boss_every - 1;
+			Some(self.bosses[milestone as usize % self.bosses.len()].clone())
+		} else {
+			None
+		};
+		WaveDefinition {
+			delay: self.delay,
+			count: count.round().max(1.) as u32,
+			min_batch: self.min_batch,
+			max_batch: max_batch.max(self.min_batch),
+			batch_interval: self.batch_interval / intensity,
+			spawners: (0..self.spawner_count)
+				.map(|i| SpawnPoint {
+					angle: angle * i as f32 + angle * 0.5 * wave as f32,
+					distance: self.spawner_distance,
+				})
+				.collect(),
+			enemies: self.enemies.clone(),
+			boss,
+		}
+	}
+}
+
+#[derive(Resource)]
+pub struct WaveScriptHandle(pub Handle<WaveScript>);
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+	type Asset = WaveScript;
+	type Settings = ();
+	type Error = Box<dyn std::error::Error + Send + Sync>;
+
+	async fn load(
+		&self,
+		reader: &mut dyn Reader,
+		_settings: &(),
+		_load_context: &mut LoadContext<'_>,
+	) -> Result<Self::Asset, Self::Error> {
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes).await?;
+		return Ok(ron::de::from_bytes(&bytes)?);
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["waves.ron"]
+	}
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct WaveDirector {
+	//1 based, 0 before the first wave starts
+	pub wave: u32,
+	pub state: WaveState,
+	//Enemies of the current wave still waiting to be spawned
+	pub remaining: u32,
+	pub endless: bool,
+	pub intensity: f32,
+	pub kill_rate: f32,
+}
+
+impl Default for WaveDirector {
+	fn default() -> Self {
+		Self {
+			wave: 0,
+			state: WaveState::Idle,
+			remaining: 0,
+			endless: false,
+			intensity: 1.,
+			kill_rate: 0.,
+		}
+	}
+}
+
+impl WaveDirector {
+	//Hands out up to `count` enemies from the current wave's budget
+	pub fn take(&mut self, count: u32) -> u32 {
+		let taken = count.min(self.remaining);
+		self.remaining -= taken;
+		return taken;
+	}
+}
+
+#[derive(Reflect)]
+pub enum WaveState {
+	Idle,
+	Delay(Timer),
+	Spawning,
+	Clearing,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn take_hands_out_no_more_than_the_budget() {
+		let mut director = WaveDirector {
+			remaining: 5,
+			..default()
+		};
+		assert_eq!(director.take(3), 3);
+		assert_eq!(director.remaining, 2);
+		assert_eq!(director.take(3), 2);
+		assert_eq!(director.remaining, 0);
+		assert_eq!(director.take(3), 0);
+	}
+}
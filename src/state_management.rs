@@ -33,3 +33,25 @@ pub struct GameOverSystems;
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ResetSystems;
+
+//Reads and records the tick's input, keeps running while a level-up draft holds the rest of the gameplay still
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerInputSystems;
+
+//Input sources fill in the tick's input before the player's intent is updated from it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputSourceSystems;
+
+//Systems drawing from the run's RandomGen take turns in this order, so a seed always hands out the same numbers
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngSystems {
+	Spawning,
+	Elites,
+	Bosses,
+	Weapons,
+	Hits,
+	Deaths,
+	Drops,
+	Damage,
+	Drafts,
+}
@@ -0,0 +1,1119 @@
+use std::f32::consts::PI;
+
+use bevy::{
+	ecs::entity_disabling::Disabled,
+	input::{
+		ButtonState,
+		gamepad::GamepadInput,
+		keyboard::{Key, KeyboardInput},
+	},
+	prelude::*,
+};
+use bevy_rapier2d::prelude::{ActiveEvents, Collider, CollisionGroups, Group, RigidBody, Velocity};
+use cataclyze::{
+	PLAYER_GROUP, PLAYER_OWNED_GROUP, PLAYER_PROJECTILE_GROUP,
+	components::{
+		ai::{ChargeAI, HoverAI},
+		archetype::EnemyArchetype,
+		death::{ChainLink, DeathScatter, Mine, ScatterPattern, ScatterStage, Targeting},
+		drops::{DropEntry, DropTable},
+		effects::Explosion,
+		elite::{Affix, Elite, Shield},
+		input::PlayerIntent,
+		spawner::SpawnBatch,
+		stats::{
+			Damage, Experience, Health, IFrames, Life, MaxHealth, ModifierOp, MoveSpeed, MoveSpeedStat, StatKind,
+			StatModifier, StatModifiers, Stats,
+		},
+		status::{StatusApplication, StatusEffects, StatusKind},
+		tags::{Enemy, Owner, Pickup, Projectile},
+		ui::BossBar,
+		utils::Lifetime,
+		weapons::{
+			Fragment, Fragmentation, Homing, ProjectileType, ScatterOnHit, Weapon, WeaponInventory, WeaponSpread,
+		},
+	},
+	plugins::{
+		GamePlugin, HeadlessPlugin,
+		player::{Dash, Player},
+	},
+	resources::{
+		enemies::EnemyPrefabs,
+		input::{Action, ActionMap, Binding, InputSource},
+		replay::{Replay, ReplayPlayback, ReplayRecorder},
+		upgrades::{LevelUpDraft, Rarity},
+		utils::{ChainStats, DamageEvent, DamageKind, DefaultProjTypes, DropPity, KillCount, RunSeed},
+	},
+	state_management::GameplayState,
+};
+
+const MAX_FRAMES: u32 = 1200;
+
+fn headless_app() -> App {
+	let mut app = App::new();
+	app.add_plugins((HeadlessPlugin::default(), GamePlugin));
+	return app;
+}
+
+//Steps the app until the condition holds, returns how many frames that took
+fn run_until(app: &mut App, mut condition: impl FnMut(&mut World) -> bool) -> Option<u32> {
+	for frame in 0..MAX_FRAMES {
+		if condition(app.world_mut()) {
+			return Some(frame);
+		}
+		app.update();
+	}
+	return None;
+}
+
+fn is_state(world: &World, state: GameplayState) -> bool {
+	return *world.resource::<State<GameplayState>>().get() == state;
+}
+
+//Waits on the main menu for the enemy roster to finish loading, then skips it
+fn start_game(app: &mut App) {
+	run_until(app, |world| {
+		is_state(world, GameplayState::Waiting) && world.resource::<EnemyPrefabs>().0.len() == 5
+	})
+	.expect("Enemy roster never loaded");
+	app.world_mut()
+		.resource_mut::<NextState<GameplayState>>()
+		.set(GameplayState::Startup);
+	run_until(app, |world| is_state(world, GameplayState::Playing)).expect("Game never started");
+}
+
+fn player_pos(world: &mut World) -> Vec3 {
+	let mut query = world.query_filtered::<&Transform, With<Player>>();
+	return query.single(world).expect("No player").translation;
+}
+
+//What a run has done so far, compared bit for bit between runs
+fn snapshot(world: &mut World) -> (u32, Vec<Vec3>) {
+	let mut enemies = world.query_filtered::<&Transform, With<Enemy>>();
+	let positions = enemies.iter(world).map(|transform| transform.translation).collect();
+	return (world.resource::<KillCount>().0, positions);
+}
+
+fn spawn_enemy(world: &mut World, archetype: &str, pos: Vec3) -> Entity {
+	let prefab = world.resource::<EnemyPrefabs>().0[archetype];
+	let enemy = world
+		.commands()
+		.entity(prefab)
+		.clone_and_spawn_with(|builder| {
+			builder.deny::<Disabled>();
+		})
+		.insert(Transform::from_translation(pos))
+		.id();
+	world.flush();
+	return enemy;
+}
+
+fn spawn_pickup(world: &mut World, pickup: Pickup, pos: Vec3) {
+	world.spawn((
+		pickup,
+		Collider::ball(4.),
+		CollisionGroups::new(PLAYER_GROUP, PLAYER_GROUP),
+		Transform::from_translation(pos),
+	));
+}
+
+//Goes through the input events, pressing the resource directly is cleared before anything reads a fresh press
+fn tap_key(world: &mut World, key_code: KeyCode, logical_key: Key) {
+	world.send_event(KeyboardInput {
+		key_code,
+		logical_key,
+		state: ButtonState::Pressed,
+		text: None,
+		repeat: false,
+		window: Entity::PLACEHOLDER,
+	});
+}
+
+#[test]
+fn chaser_dies_when_it_touches_the_player() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::X * 40.;
+	let chaser = spawn_enemy(world, "chaser", pos);
+
+	run_until(&mut app, |world| world.get_entity(chaser).is_err()).expect("Chaser never died");
+	assert_eq!(app.world().resource::<KillCount>().0, 1);
+}
+
+#[test]
+fn gunner_shoots_the_player() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 250.;
+	spawn_enemy(world, "gunner", pos);
+
+	run_until(&mut app, |world| {
+		let mut projectiles = world.query::<&Projectile>();
+		projectiles
+			.iter(world)
+			.any(|projectile| matches!(projectile.0, Owner::Enemy))
+	})
+	.expect("Gunner never fired");
+	run_until(&mut app, |world| {
+		let mut player = world.query_filtered::<(&Health, &MaxHealth), With<Player>>();
+		let (health, max) = player.single(world).unwrap();
+		health.0 < max.0
+	})
+	.expect("Gunner never hit the player");
+	let mut enemies = app.world_mut().query_filtered::<&Life, With<Enemy>>();
+	assert!(enemies.iter(app.world()).all(Life::is_alive), "Gunner shot itself");
+}
+
+#[test]
+fn timed_buff_expires_back_to_base_stats() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let mut player = world.query_filtered::<(&mut StatModifiers, &Stats), With<Player>>();
+	let (mut modifiers, stats) = player.single_mut(world).unwrap();
+	let base_speed = stats.0.move_speed;
+	modifiers.add(StatModifier::timed(
+		StatKind::MoveSpeed,
+		ModifierOp::Multiply(2.),
+		"test",
+		0.5,
+	));
+
+	let move_speed = |world: &mut World| {
+		let mut player = world.query_filtered::<&MoveSpeed, With<Player>>();
+		player.single(world).unwrap().0
+	};
+	run_until(&mut app, |world| move_speed(world) == base_speed * 2.).expect("Buff never applied");
+	run_until(&mut app, |world| move_speed(world) == base_speed).expect("Buff never expired");
+	let mut player = app.world_mut().query_filtered::<&StatModifiers, With<Player>>();
+	assert!(player.single(app.world()).unwrap().0.is_empty());
+}
+
+#[test]
+fn burn_kills_over_time_and_spreads_on_death() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 500.;
+	let burning = spawn_enemy(world, "chaser", pos);
+	let neighbour = spawn_enemy(world, "chaser", pos + Vec3::X * 30.);
+	world.get_mut::<Health>(burning).unwrap().0 = 5.;
+	world.get_mut::<StatusEffects>(burning).unwrap().apply(
+		StatusApplication {
+			kind: StatusKind::Burn,
+			stacks: 1,
+			duration: 5.,
+		},
+		Owner::Player,
+	);
+
+	run_until(&mut app, |world| world.get_entity(burning).is_err()).expect("Burn never killed");
+	let statuses = app.world().get::<StatusEffects>(neighbour).expect("Neighbour died too");
+	let burn = statuses
+		.0
+		.iter()
+		.find(|active| active.kind == StatusKind::Burn)
+		.expect("Burn didn't spread");
+	//Only what was left of the burn is passed on, still credited to the player
+	assert!(burn.duration.remaining_secs() < 5.);
+	assert_eq!(burn.instigator, Owner::Player);
+}
+
+#[test]
+fn charger_resists_explosive_damage() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 500.;
+	let chargers = [(DamageKind::Kinetic, 0.), (DamageKind::Explosive, 50.)].map(|(kind, offset)| {
+		let charger = spawn_enemy(world, "charger", pos + Vec3::X * offset);
+		world.send_event(DamageEvent {
+			source: charger,
+			target: charger,
+			amount: 40.,
+			kind,
+			crit: default(),
+			origin: pos.xy(),
+			knockback: 0.,
+			instigator: Owner::Player,
+		});
+		charger
+	});
+
+	app.update();
+	let health = chargers.map(|charger| app.world().get::<Health>(charger).unwrap().0);
+	assert_eq!(health, [60., 80.]);
+}
+
+#[test]
+fn iframes_stop_hits_stacking_and_knockback_pushes_away() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let mut query = world.query_filtered::<Entity, With<Player>>();
+	let player = query.single(world).unwrap();
+	let pos = player_pos(world).xy();
+	for _ in 0..3 {
+		world.send_event(DamageEvent {
+			source: player,
+			target: player,
+			amount: 100.,
+			kind: DamageKind::Explosive,
+			crit: default(),
+			origin: pos - Vec2::X * 50.,
+			knockback: 200.,
+			instigator: Owner::Enemy,
+		});
+	}
+
+	app.update();
+	let world = app.world();
+	assert_eq!(
+		world.get::<Health>(player).unwrap().0,
+		world.get::<MaxHealth>(player).unwrap().0 - 100.
+	);
+	assert!(world.get::<Velocity>(player).unwrap().linvel.x > 0.);
+}
+
+#[test]
+fn harmless_hits_leave_iframes_for_the_real_one() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let mut query = world.query_filtered::<Entity, With<Player>>();
+	let player = query.single(world).unwrap();
+	let pos = player_pos(world).xy();
+	//A grenade's touch followed by its explosion
+	for (amount, kind) in [(0., DamageKind::Kinetic), (30., DamageKind::Explosive)] {
+		world.send_event(DamageEvent {
+			source: player,
+			target: player,
+			amount,
+			kind,
+			crit: default(),
+			origin: pos - Vec2::X * 50.,
+			knockback: 0.,
+			instigator: Owner::Enemy,
+		});
+	}
+
+	app.update();
+	let world = app.world();
+	assert_eq!(
+		world.get::<Health>(player).unwrap().0,
+		world.get::<MaxHealth>(player).unwrap().0 - 30.
+	);
+}
+
+#[test]
+fn dash_bursts_ahead_with_iframes_and_cools_down() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let start = player_pos(app.world_mut());
+	let world = app.world_mut();
+	world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyD);
+	tap_key(world, KeyCode::ShiftLeft, Key::Shift);
+	//Input is read after the fixed tick, so the dash starts on the next frame
+	app.update();
+	app.update();
+	let world = app.world_mut();
+	let mut query = world.query_filtered::<(&Dash, &IFrames), With<Player>>();
+	let (dash, iframes) = query.single(world).unwrap();
+	assert!(dash.is_dashing() && iframes.is_active());
+
+	for _ in 0..20 {
+		app.update();
+	}
+	let world = app.world_mut();
+	assert!(player_pos(world).x > start.x + 100.);
+	let (dash, iframes) = query.single(world).unwrap();
+	assert!(!dash.is_dashing() && !iframes.is_active());
+	assert!(!dash.cooldown.finished());
+}
+
+#[test]
+fn level_up_pauses_until_an_upgrade_is_picked() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world);
+	let enemy = spawn_enemy(world, "chaser", pos + Vec3::X * 300.);
+	spawn_pickup(world, Pickup::Xp(Experience::BASE), pos);
+	run_until(&mut app, |world| world.resource::<LevelUpDraft>().is_open()).expect("Draft never opened");
+
+	let enemy_pos = app.world().get::<Transform>(enemy).unwrap().translation;
+	for _ in 0..30 {
+		app.update();
+	}
+	assert_eq!(app.world().get::<Transform>(enemy).unwrap().translation, enemy_pos);
+
+	//The same key picks a projectile outside of the draft
+	tap_key(app.world_mut(), KeyCode::Digit2, Key::Character("2".into()));
+	run_until(&mut app, |world| !world.resource::<LevelUpDraft>().is_open()).expect("Pick was ignored");
+	let world = app.world_mut();
+	let draft = world.resource::<LevelUpDraft>();
+	assert_eq!(draft.picks.values().sum::<u32>(), 1);
+	let mut query = world.query_filtered::<&Experience, With<Player>>();
+	assert_eq!(query.single(world).unwrap().level, 2);
+	let mut query = world.query_filtered::<&ProjectileType, With<Player>>();
+	assert!(matches!(query.single(world), Ok(ProjectileType::Basic { .. })));
+	for _ in 0..10 {
+		app.update();
+	}
+	assert_ne!(app.world().get::<Transform>(enemy).unwrap().translation, enemy_pos);
+}
+
+#[test]
+fn weapon_pickups_fill_the_inventory_and_level_up() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world);
+	spawn_pickup(world, Pickup::Weapon(Weapon::Spread), pos);
+	spawn_pickup(world, Pickup::Weapon(Weapon::Spread), pos);
+	let mut query = world.query_filtered::<(&WeaponInventory, &Weapon, &WeaponSpread), With<Player>>();
+	run_until(&mut app, |world| query.single(world).unwrap().0.held.len() == 2).expect("Weapon was not picked up");
+	for _ in 0..5 {
+		app.update();
+	}
+	let (inventory, weapon, spread) = query.single(app.world()).unwrap();
+	let levels: Vec<_> = inventory.held.iter().map(|held| (held.weapon, held.level)).collect();
+	assert_eq!(levels, [(Weapon::Auto, 1), (Weapon::Spread, 2)]);
+	assert_eq!(*weapon, Weapon::Spread);
+	assert_eq!(spread.shot_count, WeaponSpread::default().shot_count + 1);
+
+	tap_key(app.world_mut(), KeyCode::KeyZ, Key::Character("z".into()));
+	app.update();
+	app.update();
+	assert_eq!(*query.single(app.world()).unwrap().1, Weapon::Auto);
+}
+
+fn kill(world: &mut World, enemy: Entity) {
+	world.send_event(DamageEvent {
+		source: enemy,
+		target: enemy,
+		amount: 10000.,
+		kind: DamageKind::Kinetic,
+		crit: default(),
+		origin: Vec2::ZERO,
+		knockback: 0.,
+		instigator: Owner::Player,
+	});
+}
+
+#[test]
+fn drop_tables_guarantee_drops_and_build_pity() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	//Wave enemies can't drop anything either, so only the chasers spawned here matter
+	let world = app.world_mut();
+	let dry = DropTable {
+		chance: 0.,
+		guaranteed: 0,
+		entries: vec![DropEntry::new(Pickup::Stats, Rarity::Common)],
+	};
+	let prefabs: Vec<_> = world.resource::<EnemyPrefabs>().0.values().copied().collect();
+	for prefab in prefabs {
+		world.entity_mut(prefab).insert(dry.clone());
+	}
+	let prefab = world.resource::<EnemyPrefabs>().0["chaser"];
+	world.entity_mut(prefab).insert(DropTable {
+		guaranteed: 2,
+		..dry.clone()
+	});
+	let pos = player_pos(world) + Vec3::Y * 500.;
+	let chaser = spawn_enemy(world, "chaser", pos);
+	kill(world, chaser);
+	let mut pickups = world.query::<&Pickup>();
+	run_until(&mut app, |world| {
+		pickups
+			.iter(world)
+			.filter(|pickup| matches!(pickup, Pickup::Stats))
+			.count() == 2
+	})
+	.expect("Guaranteed drops never spawned");
+	assert_eq!(app.world().resource::<DropPity>().0, 0);
+
+	//Nothing can drop now, so each death adds to the pity instead
+	let world = app.world_mut();
+	world.entity_mut(prefab).insert(dry);
+	let pity = world.resource::<DropPity>().0;
+	let chasers = [0., 50.].map(|offset| {
+		let chaser = spawn_enemy(world, "chaser", pos + Vec3::X * offset);
+		kill(world, chaser);
+		chaser
+	});
+	run_until(&mut app, |world| {
+		chasers.iter().all(|chaser| world.get_entity(*chaser).is_err())
+	})
+	.expect("Chasers never died");
+	for _ in 0..5 {
+		app.update();
+	}
+	assert!(app.world().resource::<DropPity>().0 >= pity + 2);
+}
+
+#[test]
+fn elites_take_on_their_affixes_and_split_on_death() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 500.;
+	let elite = spawn_enemy(world, "chaser", pos);
+	world
+		.entity_mut(elite)
+		.insert(Elite(vec![Affix::Shielded, Affix::Volatile, Affix::Splitting]));
+	let hasted = spawn_enemy(world, "chaser", pos + Vec3::X * 200.);
+	world.entity_mut(hasted).insert(Elite(vec![Affix::Hasted]));
+	app.update();
+	app.update();
+
+	let world = app.world();
+	assert_eq!(world.get::<MaxHealth>(elite).unwrap().0, 100.);
+	assert_eq!(world.get::<Shield>(elite).unwrap().0, 50.);
+	let stage = world.get::<DeathScatter>(elite).unwrap().stages[0];
+	assert_eq!(stage.damage, 80.);
+	let ScatterPattern::Explosion { range, .. } = stage.pattern else {
+		panic!("Volatile changed the chaser's explosion");
+	};
+	assert_eq!(range, 150.);
+	assert_eq!(world.get::<Children>(elite).map_or(0, |children| children.len()), 3);
+	assert_eq!(world.get::<MaxHealth>(hasted).unwrap().0, 100.);
+	assert_eq!(world.get::<MoveSpeedStat>(hasted).unwrap().0, 64.);
+	assert_eq!(world.get::<StatModifiers>(hasted).unwrap().0.len(), 1);
+
+	kill(app.world_mut(), elite);
+	let mut chasers = app
+		.world_mut()
+		.query::<(Entity, &Transform, &EnemyArchetype, &MaxHealth)>();
+	run_until(&mut app, |world| {
+		let splits = chasers
+			.iter(world)
+			.filter(|(entity, transform, archetype, max_health)| {
+				*entity != elite
+					&& archetype.0 == "chaser"
+					&& max_health.0 == 25.
+					&& transform.translation.distance(pos) < 40.
+			});
+		splits.count() == 2
+	})
+	.expect("Elite never split");
+}
+
+#[test]
+fn boss_changes_phase_with_health_and_gets_a_health_bar() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 300.;
+	let boss = spawn_enemy(world, "warden", pos);
+	app.update();
+	app.update();
+
+	let world = app.world_mut();
+	assert!(world.get::<HoverAI>(boss).is_some());
+	assert!(world.get::<ChargeAI>(boss).is_none());
+	let mut bars = world.query::<&BossBar>();
+	assert!(bars.iter(world).any(|bar| bar.0 == boss), "No boss health bar");
+
+	world.send_event(DamageEvent {
+		source: boss,
+		target: boss,
+		amount: 900.,
+		kind: DamageKind::Kinetic,
+		crit: default(),
+		origin: pos.xy(),
+		knockback: 0.,
+		instigator: Owner::Player,
+	});
+	run_until(&mut app, |world| world.get::<ChargeAI>(boss).is_some()).expect("Boss never started charging");
+	assert!(app.world().get::<HoverAI>(boss).is_none());
+
+	//The second phase fires a spread at the player on a timer
+	let mut projectiles = app.world_mut().query::<&Projectile>();
+	run_until(&mut app, |world| {
+		projectiles
+			.iter(world)
+			.any(|projectile| matches!(projectile.0, Owner::Enemy))
+	})
+	.expect("Boss never fired");
+	//Barrages are the boss's own doing, so nothing they kill starts or joins a chain
+	let mut links = app.world_mut().query_filtered::<&ChainLink, With<Projectile>>();
+	run_until(&mut app, |world| links.iter(world).any(ChainLink::is_enemy)).expect("Barrage joined a chain");
+
+	kill(app.world_mut(), boss);
+	run_until(&mut app, |world| bars.iter(world).next().is_none()).expect("Boss health bar stayed up");
+}
+
+#[test]
+fn move_speed_modifier_changes_how_fast_the_player_moves() {
+	let distance = |modifier: Option<StatModifier>| {
+		let mut app = headless_app();
+		start_game(&mut app);
+		let world = app.world_mut();
+		if let Some(modifier) = modifier {
+			let mut player = world.query_filtered::<&mut StatModifiers, With<Player>>();
+			player.single_mut(world).unwrap().add(modifier);
+		}
+		let start = player_pos(world);
+		world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyD);
+		for _ in 0..60 {
+			app.update();
+		}
+		return player_pos(app.world_mut()).x - start.x;
+	};
+	let base = distance(None);
+	let hasted = distance(Some(StatModifier::permanent(
+		StatKind::MoveSpeed,
+		ModifierOp::Multiply(2.),
+		"test",
+	)));
+	assert!(base > 1.);
+	assert!(
+		hasted > base * 1.5,
+		"Moved {hasted} with the modifier against {base} without"
+	);
+}
+
+#[test]
+fn grenade_scatter_spawns_an_explosion() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let grenade = world
+		.spawn((
+			DeathScatter::from(ScatterStage {
+				count: 1,
+				pattern: ScatterPattern::Explosion {
+					range: 100.,
+					speed: 300.,
+				},
+				damage: 100.,
+				..default()
+			}),
+			Lifetime::new(0.5),
+			Transform::from_xyz(200., 200., 0.),
+		))
+		.id();
+
+	run_until(&mut app, |world| {
+		let mut explosions = world.query_filtered::<(), With<Explosion>>();
+		explosions.iter(world).next().is_some()
+	})
+	.expect("Grenade never exploded");
+	assert!(app.world().get_entity(grenade).is_err());
+}
+
+#[test]
+fn player_grenade_payload_stays_on_the_players_side() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	if let ProjectileType::Grenade { payload, .. } = &mut world.resource_mut::<DefaultProjTypes>().grenade {
+		*payload = Some(ScatterStage {
+			count: 6,
+			pattern: ScatterPattern::Ring { offset: 0. },
+			damage: 10.,
+			..default()
+		});
+	}
+	tap_key(world, KeyCode::Digit4, Key::Character("4".into()));
+	run_until(&mut app, |world| {
+		let mut query = world.query_filtered::<&ProjectileType, With<Player>>();
+		matches!(
+			query.single(world),
+			Ok(ProjectileType::Grenade { payload: Some(_), .. })
+		)
+	})
+	.expect("Grenade never selected");
+	app.world_mut()
+		.resource_mut::<ButtonInput<MouseButton>>()
+		.press(MouseButton::Left);
+
+	let mut shrapnel = app
+		.world_mut()
+		.query_filtered::<(&Projectile, &CollisionGroups), (With<Lifetime>, Without<DeathScatter>)>();
+	let mut explosions = app.world_mut().query::<(&Explosion, &CollisionGroups)>();
+	run_until(&mut app, |world| {
+		shrapnel.iter(world).count() >= 6 && explosions.iter(world).next().is_some()
+	})
+	.expect("Grenade payload never went off");
+	let world = app.world_mut();
+	for (proj, groups) in shrapnel.iter(world) {
+		assert_eq!(proj.0, Owner::Player);
+		assert_eq!(groups.memberships, PLAYER_PROJECTILE_GROUP);
+		assert!(!groups.filters.intersects(PLAYER_GROUP));
+	}
+	for (explosion, groups) in explosions.iter(world) {
+		assert_eq!(explosion.owner, Owner::Player);
+		assert!(!groups.filters.intersects(PLAYER_GROUP));
+	}
+}
+
+#[test]
+fn scatter_stages_go_off_in_turn_before_despawning() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	//Slow enough that every explosion is still growing when the last one goes off
+	let explosion = ScatterStage {
+		count: 1,
+		pattern: ScatterPattern::Explosion {
+			range: 500.,
+			speed: 50.,
+		},
+		damage: 10.,
+		..default()
+	};
+	let scatter = DeathScatter {
+		stages: vec![
+			explosion,
+			ScatterStage {
+				count: 8,
+				pattern: ScatterPattern::Spread {
+					arc: 360.,
+					targeting: Targeting::Forward,
+				},
+				delay: 0.3,
+				..explosion
+			},
+			ScatterStage {
+				delay: 0.5,
+				repeat: 1,
+				interval: 0.25,
+				..explosion
+			},
+		],
+	};
+	let grenade = world
+		.spawn((scatter, Life(false, true), Transform::from_xyz(-200., 200., 0.)))
+		.id();
+
+	let mut explosions = world.query_filtered::<(), With<Explosion>>();
+	let mut projectiles = world.query::<&Projectile>();
+	let mut shrapnel = |world: &mut World| {
+		projectiles
+			.iter(world)
+			.filter(|proj| matches!(proj.0, Owner::Enemy))
+			.count()
+	};
+	app.update();
+	let world = app.world_mut();
+	assert_eq!(explosions.iter(world).count(), 1);
+	assert_eq!(shrapnel(world), 0);
+
+	run_until(&mut app, |world| shrapnel(world) == 8).expect("Shrapnel never released");
+	assert!(
+		app.world().get_entity(grenade).is_ok(),
+		"Despawned with stages left to go"
+	);
+	run_until(&mut app, |world| world.get_entity(grenade).is_err()).expect("Never despawned");
+	//Both repeats of the last stage went off before it did
+	assert_eq!(explosions.iter(app.world()).count(), 3);
+}
+
+#[test]
+fn ring_homing_mine_and_chain_patterns_go_off() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let origin = Vec3::new(-200., 200., 0.);
+	let first = spawn_enemy(world, "chaser", origin + Vec3::new(52., 30., 0.));
+	let second = spawn_enemy(world, "chaser", origin + Vec3::new(52., 110., 0.));
+	let out_of_reach = spawn_enemy(world, "chaser", origin + Vec3::new(-300., 0., 0.));
+	let stage = |count, pattern| ScatterStage {
+		count,
+		pattern,
+		damage: 10.,
+		..default()
+	};
+	let scatter = DeathScatter {
+		stages: vec![
+			stage(6, ScatterPattern::Ring { offset: 0. }),
+			stage(
+				4,
+				ScatterPattern::Homing {
+					turn_rate: 180.,
+					range: 300.,
+				},
+			),
+			stage(
+				3,
+				ScatterPattern::Mines {
+					radius: 40.,
+					fuse: 0.5,
+					range: 30.,
+				},
+			),
+			stage(
+				2,
+				ScatterPattern::Chain {
+					range: 100.,
+					falloff: 0.5,
+				},
+			),
+		],
+	};
+	let enemies = [first, second, out_of_reach];
+	let health = |world: &World| enemies.map(|enemy| world.get::<Health>(enemy).expect("Enemy despawned").0);
+	let before = health(world);
+	world.spawn((scatter, Life(false, true), Transform::from_translation(origin)));
+
+	app.update();
+	app.update();
+	let world = app.world_mut();
+	let mut projectiles = world.query::<(&Projectile, Has<Homing>)>();
+	let shrapnel: Vec<_> = projectiles
+		.iter(world)
+		.filter(|(proj, _)| matches!(proj.0, Owner::Enemy))
+		.map(|(_, homing)| homing)
+		.collect();
+	assert_eq!(shrapnel.len(), 10);
+	assert_eq!(shrapnel.iter().filter(|homing| **homing).count(), 4);
+	let mut mines = world.query_filtered::<(), With<Mine>>();
+	assert_eq!(mines.iter(world).count(), 3);
+
+	//The chain jumps to the closest enemy, then the one closest to that for half as much
+	let after = health(world);
+	let lost: Vec<_> = before.iter().zip(after).map(|(before, after)| before - after).collect();
+	assert!(lost[0] > lost[1] && lost[1] > 0., "Chain damage was {lost:?}");
+	assert_eq!(lost[2], 0.);
+
+	run_until(&mut app, |world| mines.iter(world).next().is_none()).expect("Mines never went off");
+}
+
+#[test]
+fn homing_projectiles_steer_and_retarget_after_a_kill() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	tap_key(app.world_mut(), KeyCode::Digit5, Key::Character("5".into()));
+	run_until(&mut app, |world| {
+		let mut query = world.query_filtered::<&ProjectileType, With<Player>>();
+		matches!(query.single(world), Ok(ProjectileType::Homing { .. }))
+	})
+	.expect("Homing never selected");
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 300.;
+	let first = spawn_enemy(world, "chaser", pos + Vec3::X * 100.);
+	let second = spawn_enemy(world, "chaser", pos - Vec3::X * 150.);
+	let proj = world
+		.spawn((
+			Homing::new(90., 200., Owner::Player),
+			Velocity::linear(Vec2::Y * 200.),
+			Transform::from_translation(pos),
+		))
+		.id();
+	app.update();
+	let world = app.world();
+	assert_eq!(world.get::<Homing>(proj).unwrap().target, Some(first));
+	let vel = world.get::<Velocity>(proj).unwrap().linvel;
+	assert!(vel.x > 0., "Turned away from the target: {vel}");
+	assert!((vel.length() - 200.).abs() < 0.01, "Speed changed: {vel}");
+
+	kill(app.world_mut(), first);
+	run_until(&mut app, |world| {
+		world.get::<Homing>(proj).unwrap().target == Some(second)
+	})
+	.expect("Never retargeted");
+	run_until(&mut app, |world| world.get::<Velocity>(proj).unwrap().linvel.x < 0.).expect("Never turned around");
+}
+
+#[test]
+fn fragmenting_projectiles_split_on_hit_up_to_their_generation_cap() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let pos = player_pos(world) + Vec3::Y * 150.;
+	spawn_enemy(world, "chaser", pos + Vec3::Y * 60.);
+	let fragmentation = Fragmentation {
+		count: 4,
+		arc: 90.,
+		damage: 0.5,
+		generations: 1,
+	};
+	world.spawn((
+		Projectile::player(),
+		Damage(40.),
+		fragmentation,
+		Transform::from_translation(pos),
+		RigidBody::Dynamic,
+		Velocity::linear(Vec2::Y * 300.),
+		Collider::ball(2.),
+		CollisionGroups::new(PLAYER_PROJECTILE_GROUP, Group::ALL ^ PLAYER_OWNED_GROUP),
+		ActiveEvents::COLLISION_EVENTS,
+	));
+
+	let mut fragments = app.world_mut().query::<(&Fragment, &Damage)>();
+	run_until(&mut app, |world| fragments.iter(world).next().is_some()).expect("Never fragmented");
+	let world = app.world_mut();
+	assert_eq!(fragments.iter(world).count(), 4);
+	for (fragment, damage) in fragments.iter(world) {
+		assert_eq!(fragment.generation, 1);
+		assert_eq!(damage.0, 20.);
+	}
+	//The fragments carry the same fragmentation but are already at the cap
+	for _ in 0..60 {
+		app.update();
+		assert!(
+			fragments
+				.iter(app.world())
+				.all(|(fragment, _)| fragment.generation == 1)
+		);
+	}
+}
+
+#[test]
+fn hit_scatter_kills_count_towards_a_chain() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let mut query = world.query_filtered::<&mut ScatterOnHit, With<Player>>();
+	*query.single_mut(world).unwrap() = ScatterOnHit {
+		chance: 1.,
+		damage: 1000.,
+		range: 60.,
+	};
+	let pos = player_pos(world) + Vec3::Y * 150.;
+	spawn_enemy(world, "chaser", pos + Vec3::Y * 60.);
+	spawn_enemy(world, "chaser", pos + Vec3::new(20., 70., 0.));
+	world.spawn((
+		Projectile::player(),
+		Damage(1.),
+		Transform::from_translation(pos),
+		RigidBody::Dynamic,
+		Velocity::linear(Vec2::Y * 300.),
+		Collider::ball(2.),
+		CollisionGroups::new(PLAYER_PROJECTILE_GROUP, Group::ALL ^ PLAYER_OWNED_GROUP),
+		ActiveEvents::COLLISION_EVENTS,
+	));
+
+	run_until(&mut app, |world| world.resource::<KillCount>().0 == 2).expect("Hit scatter never killed both");
+	//The scatter starts the chain, so what it kills sits a link down like any other scatter kill
+	let chains = app.world().resource::<ChainStats>();
+	assert_eq!(chains.longest, 2);
+	assert_eq!(chains.deepest, 1);
+	assert_eq!(chains.chain_kills, 1);
+}
+
+#[test]
+fn first_wave_spawns_enemies() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	run_until(&mut app, |world| {
+		let mut enemies = world.query_filtered::<(), With<Enemy>>();
+		enemies.iter(world).next().is_some()
+	})
+	.expect("No enemies spawned");
+}
+
+#[test]
+fn missing_prefabs_keep_their_place_in_the_wave() {
+	let mut app = headless_app();
+	start_game(&mut app);
+	app.world_mut().resource_mut::<EnemyPrefabs>().0.clear();
+
+	let mut batches = app.world_mut().query::<&SpawnBatch>();
+	let mut queued = |world: &mut World| batches.iter(world).map(|batch| batch.0).sum::<u32>();
+	run_until(&mut app, |world| queued(world) > 0).expect("Nothing was queued");
+	for _ in 0..300 {
+		let before = queued(app.world_mut());
+		app.update();
+		assert!(queued(app.world_mut()) >= before, "Wave budget spent without spawning");
+	}
+	let mut enemies = app.world_mut().query_filtered::<(), With<Enemy>>();
+	assert_eq!(enemies.iter(app.world()).count(), 0);
+}
+
+#[test]
+fn same_seed_replays_the_same_run() {
+	let run = |seed| {
+		let mut app = headless_app();
+		app.insert_resource(RunSeed::fixed(seed));
+		start_game(&mut app);
+		for _ in 0..600 {
+			app.update();
+		}
+		return snapshot(app.world_mut());
+	};
+
+	let first = run(42);
+	assert!(!first.1.is_empty());
+	assert_eq!(first, run(42));
+	assert_ne!(first, run(7));
+}
+
+#[test]
+fn replay_reproduces_the_recorded_run() {
+	let mut app = headless_app();
+	app.insert_resource(RunSeed::fixed(3));
+	start_game(&mut app);
+	for frame in 0..600 {
+		let world = app.world_mut();
+		let mut keys = world.resource_mut::<ButtonInput<KeyCode>>();
+		match frame {
+			0 => keys.press(KeyCode::KeyD),
+			150 => {
+				keys.release(KeyCode::KeyD);
+				keys.press(KeyCode::KeyW);
+				keys.press(KeyCode::Digit4);
+			}
+			151 => keys.release(KeyCode::Digit4),
+			_ => {}
+		}
+		let mut mouse = world.resource_mut::<ButtonInput<MouseButton>>();
+		if frame % 100 == 0 {
+			mouse.press(MouseButton::Left);
+		} else if frame % 100 == 50 {
+			mouse.release(MouseButton::Left);
+		}
+		app.update();
+	}
+	let recorded = (snapshot(app.world_mut()), player_pos(app.world_mut()));
+	let bytes = app.world().resource::<ReplayRecorder>().replay.to_bytes().unwrap();
+
+	let replay = Replay::from_bytes(&bytes).expect("Replay failed to decode");
+	assert_eq!(replay, app.world().resource::<ReplayRecorder>().replay);
+	assert_eq!(replay.seed, 3);
+
+	let mut app = headless_app();
+	app.insert_resource(RunSeed::fixed(replay.seed));
+	app.insert_resource(ReplayPlayback::new(replay));
+	app.insert_resource(InputSource::Replay);
+	start_game(&mut app);
+	for _ in 0..600 {
+		app.update();
+	}
+	assert_eq!(recorded, (snapshot(app.world_mut()), player_pos(app.world_mut())));
+	assert_ne!(recorded.1, Vec3::ZERO);
+}
+
+#[test]
+fn replay_reproduces_a_run_with_elites_and_scatter_kills() {
+	//A pack of elites packed close enough for their death scatters to set each other off
+	let spawn_pack = |app: &mut App| {
+		let world = app.world_mut();
+		let pos = player_pos(world) + Vec3::Y * 160.;
+		for i in 0..8 {
+			let offset = Vec2::from_angle(i as f32 * PI / 4.) * 25.;
+			let enemy = spawn_enemy(world, "chaser", pos + offset.extend(0.));
+			if i % 2 == 0 {
+				world
+					.entity_mut(enemy)
+					.insert(Elite(vec![Affix::Vampiric, Affix::Volatile, Affix::Teleporting]));
+			}
+		}
+	};
+	let outcome = |app: &mut App| {
+		let world = app.world_mut();
+		let chains = world.resource::<ChainStats>();
+		let chains = (chains.chain_kills, chains.longest, chains.deepest);
+		let mut healths = world.query_filtered::<&Health, With<Enemy>>();
+		let healths: Vec<f32> = healths.iter(world).map(|health| health.0).collect();
+		return (snapshot(world), player_pos(world), chains, healths);
+	};
+
+	let mut app = headless_app();
+	app.insert_resource(RunSeed::fixed(11));
+	start_game(&mut app);
+	spawn_pack(&mut app);
+	for frame in 0..400 {
+		let world = app.world_mut();
+		let mut keys = world.resource_mut::<ButtonInput<KeyCode>>();
+		match frame {
+			0 => keys.press(KeyCode::Digit4),
+			1 => keys.release(KeyCode::Digit4),
+			_ => {}
+		}
+		let mut mouse = world.resource_mut::<ButtonInput<MouseButton>>();
+		match frame {
+			10 => mouse.press(MouseButton::Left),
+			200 => mouse.release(MouseButton::Left),
+			_ => {}
+		}
+		app.update();
+	}
+	let recorded = outcome(&mut app);
+	assert!(recorded.0.0 >= 2, "Only {} kills", recorded.0.0);
+	assert!(recorded.2.0 > 0, "No scatter kills");
+	let replay = app.world().resource::<ReplayRecorder>().replay.clone();
+
+	let mut app = headless_app();
+	app.insert_resource(RunSeed::fixed(replay.seed));
+	app.insert_resource(ReplayPlayback::new(replay));
+	app.insert_resource(InputSource::Replay);
+	start_game(&mut app);
+	spawn_pack(&mut app);
+	for _ in 0..400 {
+		app.update();
+	}
+	assert_eq!(recorded, outcome(&mut app));
+}
+
+#[test]
+fn rebound_action_moves_the_player() {
+	let mut app = headless_app();
+	start_game(&mut app);
+	app.world_mut()
+		.resource_mut::<ActionMap>()
+		.rebind(Action::MoveRight, vec![Binding::Key(KeyCode::ArrowRight)]);
+
+	let start = player_pos(app.world_mut());
+	app.world_mut()
+		.resource_mut::<ButtonInput<KeyCode>>()
+		.press(KeyCode::ArrowRight);
+	for _ in 0..30 {
+		app.update();
+	}
+	assert!(player_pos(app.world_mut()).x > start.x + 1.);
+}
+
+#[test]
+fn gamepad_moves_and_aims_with_assist() {
+	let mut app = headless_app();
+	start_game(&mut app);
+
+	let world = app.world_mut();
+	let start = player_pos(world);
+	//Slightly off the right stick, close enough for aim assist to pull towards it
+	let offset = Vec2::from_angle(0.2) * 400.;
+	spawn_enemy(world, "chaser", start + offset.extend(0.));
+	let mut gamepad = Gamepad::default();
+	let analog = gamepad.analog_mut();
+	analog.set(GamepadInput::Axis(GamepadAxis::LeftStickY), 1.);
+	analog.set(GamepadInput::Axis(GamepadAxis::RightStickX), 1.);
+	world.spawn(gamepad);
+
+	for _ in 0..10 {
+		app.update();
+	}
+	let world = app.world_mut();
+	let mut query = world.query_filtered::<(&Transform, &PlayerIntent), With<Player>>();
+	let (transform, intent) = query.single(world).unwrap();
+	assert!(transform.translation.y > start.y + 1.);
+	let aim = intent.aim_dir.to_angle();
+	assert!(aim > 0.1 && aim < 0.2, "aim {aim} not pulled towards the enemy");
+}